@@ -0,0 +1,214 @@
+use crate::util::Accuracy;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use std::fmt;
+
+/// Two predictions for the same game, and whether the away team actually won.
+#[derive(Debug, Clone, Copy)]
+pub struct Paired {
+    /// The prediction we're comparing against, usually the official odds.
+    pub baseline: f64,
+    pub ours: f64,
+    pub actual: u8,
+}
+
+/// A point estimate with a confidence interval around it.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Interval {
+    pub estimate: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+/// Confidence intervals on each [`Accuracy`] metric.
+#[derive(Debug, Serialize)]
+pub struct Intervals {
+    pub correct: Interval,
+    pub error: Interval,
+    pub log_loss: Interval,
+}
+
+/// How much better (lower) our error and log loss are than the baseline's on the same games.
+#[derive(Debug, Serialize)]
+pub struct Difference {
+    /// Our error minus the baseline's.
+    pub error: Interval,
+    /// The chance of a difference in error at least this far from zero if neither is better.
+    pub error_p: f64,
+    /// Our log loss minus the baseline's.
+    pub log_loss: Interval,
+    pub log_loss_p: f64,
+}
+
+/// The results of [`bootstrap`].
+#[derive(Debug, Serialize)]
+pub struct Bootstrap {
+    pub resamples: usize,
+    pub confidence: f64,
+    pub baseline: Intervals,
+    pub ours: Intervals,
+    pub difference: Difference,
+}
+
+/// Resamples `games` with replacement `resamples` times to put `confidence` intervals (e.g.
+/// 0.95) on each metric, and to test whether our predictions beat the baseline. Resampling whole
+/// games keeps the test paired: both predictions are always scored on the same games.
+pub fn bootstrap(games: &[Paired], resamples: usize, confidence: f64, seed: u64) -> Bootstrap {
+    let score = |sample: &mut dyn Iterator<Item = &Paired>| {
+        let mut baseline = Accuracy::default();
+        let mut ours = Accuracy::default();
+        for game in sample {
+            baseline.record(game.baseline, game.actual);
+            ours.record(game.ours, game.actual);
+        }
+        [
+            baseline.correct(),
+            baseline.error(),
+            baseline.log_loss(),
+            ours.correct(),
+            ours.error(),
+            ours.log_loss(),
+            ours.error() - baseline.error(),
+            ours.log_loss() - baseline.log_loss(),
+        ]
+    };
+
+    let estimates = score(&mut games.iter());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut samples = vec![Vec::with_capacity(resamples); estimates.len()];
+    for _ in 0..resamples {
+        let mut sample = (0..games.len()).map(|_| &games[rng.gen_range(0, games.len())]);
+        for (samples, x) in samples.iter_mut().zip(&score(&mut sample)) {
+            samples.push(*x);
+        }
+    }
+    for samples in &mut samples {
+        samples.sort_by(f64::total_cmp);
+    }
+
+    let interval = |i: usize| percentile_interval(estimates[i], &samples[i], confidence);
+    Bootstrap {
+        resamples,
+        confidence,
+        baseline: Intervals {
+            correct: interval(0),
+            error: interval(1),
+            log_loss: interval(2),
+        },
+        ours: Intervals {
+            correct: interval(3),
+            error: interval(4),
+            log_loss: interval(5),
+        },
+        difference: Difference {
+            error: interval(6),
+            error_p: p_value(&samples[6]),
+            log_loss: interval(7),
+            log_loss_p: p_value(&samples[7]),
+        },
+    }
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn percentile_interval(estimate: f64, sorted: &[f64], confidence: f64) -> Interval {
+    if sorted.is_empty() {
+        return Interval {
+            estimate,
+            low: estimate,
+            high: estimate,
+        };
+    }
+    let last = sorted.len() - 1;
+    let at = |q: f64| sorted[((q * last as f64).round() as usize).min(last)];
+    Interval {
+        estimate,
+        low: at((1.0 - confidence) / 2.0),
+        high: at(f64::midpoint(1.0, confidence)),
+    }
+}
+
+/// A two-sided bootstrap p-value for a difference of zero.
+#[allow(clippy::cast_precision_loss)]
+fn p_value(sorted: &[f64]) -> f64 {
+    if sorted.is_empty() {
+        return 1.0;
+    }
+    let below = sorted.iter().filter(|x| **x <= 0.0).count();
+    let above = sorted.iter().filter(|x| **x >= 0.0).count();
+    (2.0 * below.min(above) as f64 / sorted.len() as f64).min(1.0)
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.4} [{:.4}, {:.4}]",
+            self.estimate, self.low, self.high
+        )
+    }
+}
+
+impl fmt::Display for Intervals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "correct: {}, error: {}, log loss: {}",
+            self.correct, self.error, self.log_loss
+        )
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error: {} (p = {:.3}), log loss: {} (p = {:.3})",
+            self.error, self.error_p, self.log_loss, self.log_loss_p
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bootstrap, Paired};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_identical() {
+        let games = (0..100)
+            .map(|i| Paired {
+                baseline: 0.6,
+                ours: 0.6,
+                actual: u8::from(i % 3 != 0),
+            })
+            .collect::<Vec<_>>();
+        let result = bootstrap(&games, 200, 0.95, 0);
+        assert_approx_eq!(result.difference.error.low, 0.0);
+        assert_approx_eq!(result.difference.error.high, 0.0);
+        assert_approx_eq!(result.difference.error_p, 1.0);
+        assert!(result.ours.error.low <= result.ours.error.estimate);
+        assert!(result.ours.error.estimate <= result.ours.error.high);
+    }
+
+    #[test]
+    fn test_better() {
+        let games = (0..200)
+            .map(|i| {
+                let actual = u8::from(i % 2 == 0);
+                Paired {
+                    baseline: 0.5,
+                    ours: if actual == 1 { 0.7 } else { 0.3 },
+                    actual,
+                }
+            })
+            .collect::<Vec<_>>();
+        let result = bootstrap(&games, 200, 0.95, 0);
+        assert_approx_eq!(result.difference.error.estimate, 0.09 - 0.25);
+        assert!(result.difference.error.high < 0.0);
+        assert!(result.difference.error_p < 0.01);
+    }
+}
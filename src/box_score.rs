@@ -0,0 +1,526 @@
+use crate::database::Player;
+use crate::event::{GameEvent, GameObserver};
+use crate::feed::{Feed, Play};
+use crate::game::{Playable, Score};
+use crate::params::ModelParams;
+use crate::pitch::PitchModel;
+use crate::util::AwayHome;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+/// The box score of one simulated game, from [`Playable::simulate_with_result`].
+#[derive(Debug, Default, Serialize)]
+pub struct GameResult {
+    pub score: Score,
+    /// Runs scored by each team in each inning they batted.
+    pub linescore: AwayHome<Vec<u8>>,
+    /// Runs the Sun 2 took off each team's score, so the runs they scored are their final score
+    /// plus these.
+    pub sun_2: AwayHome<u32>,
+    /// Each team's batters, in lineup order, followed by any who joined mid-game through the
+    /// weather.
+    pub batting: AwayHome<Vec<BattingLine>>,
+    pub pitching: AwayHome<PitchingLine>,
+}
+
+/// A batter's stats for one or more games.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct BattingLine {
+    pub id: Uuid,
+    pub name: String,
+    pub plate_appearances: u32,
+    pub hits: u32,
+    pub doubles: u32,
+    pub triples: u32,
+    pub home_runs: u32,
+    pub walks: u32,
+    pub strikeouts: u32,
+    /// Outs on balls in play, apart from double plays and fielder's choices.
+    pub fly_outs: u32,
+    pub ground_outs: u32,
+    /// Double plays the batter hit into.
+    pub double_plays: u32,
+    pub runs_batted_in: u32,
+    pub runs: u32,
+    pub stolen_bases: u32,
+    pub caught_stealing: u32,
+}
+
+/// A pitcher's stats for one or more games. Every run is earned, since there are no errors.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PitchingLine {
+    pub id: Uuid,
+    pub name: String,
+    pub pitches: u32,
+    pub strikeouts: u32,
+    pub earned_runs: u32,
+}
+
+impl GameResult {
+    pub(crate) fn new(game: &Playable) -> GameResult {
+        let batting = |lineup: &[Player; 9]| lineup.iter().map(BattingLine::new).collect();
+        GameResult {
+            score: Score::default(),
+            linescore: AwayHome::default(),
+            sun_2: AwayHome::default(),
+            batting: AwayHome {
+                away: batting(&game.lineups.away),
+                home: batting(&game.lineups.home),
+            },
+            pitching: game.pitchers.map(PitchingLine::new),
+        }
+    }
+
+    /// The box score of an archived game of `game`, from its play-by-play. Runs scored aren't
+    /// credited to anyone, since the feed doesn't say which runners crossed the plate; a batter's
+    /// runs batted in are the runs scored on their plays other than double plays, so a stolen home
+    /// isn't one. Plays by players missing from the lineups, e.g. after a substitution, only count
+    /// toward the score.
+    pub fn from_feed(game: &Playable, feed: &Feed) -> GameResult {
+        let mut result = GameResult::new(game);
+        for event in &feed.events {
+            let (before, after) = (&event.before, &event.after);
+            if event.play == Play::HalfInningStart {
+                GameResult::hitting(&mut result.linescore, after.bottom).push(0);
+                continue;
+            }
+            let bottom = before.bottom;
+            let runs = if bottom {
+                after.score.home.saturating_sub(before.score.home)
+            } else {
+                after.score.away.saturating_sub(before.score.away)
+            };
+            if let Some(inning) = GameResult::hitting(&mut result.linescore, bottom).last_mut() {
+                *inning = inning.saturating_add(u8::try_from(runs).unwrap_or(u8::MAX));
+            }
+            let pitching = GameResult::fielding(&mut result.pitching, bottom);
+            if Some(pitching.id) == event.pitcher {
+                pitching.pitches += u32::from(event.play.pitch().is_some());
+                pitching.earned_runs += u32::from(runs);
+                if let Play::Strikeout { .. } = event.play {
+                    pitching.strikeouts += 1;
+                }
+            }
+
+            if let Play::StolenBase { .. } | Play::CaughtStealing { .. } = event.play {
+                let line = event.runner.and_then(|runner| {
+                    GameResult::hitting(&mut result.batting, bottom)
+                        .iter_mut()
+                        .find(|line| line.id == runner)
+                });
+                if let Some(line) = line {
+                    if let Play::StolenBase { .. } = event.play {
+                        line.stolen_bases += 1;
+                    } else {
+                        line.caught_stealing += 1;
+                    }
+                }
+                continue;
+            }
+
+            let line = match event.batter {
+                Some(batter) => GameResult::hitting(&mut result.batting, bottom)
+                    .iter_mut()
+                    .find(|line| line.id == batter),
+                None => None,
+            };
+            let Some(line) = line else {
+                continue;
+            };
+            match event.play {
+                Play::Walk => line.walks += 1,
+                Play::Strikeout { .. } => line.strikeouts += 1,
+                Play::DoublePlay => line.double_plays += 1,
+                Play::Hit { bases } => {
+                    line.hits += 1;
+                    match bases {
+                        2 => line.doubles += 1,
+                        3 => line.triples += 1,
+                        _ => {}
+                    }
+                }
+                Play::HomeRun { .. } => {
+                    line.hits += 1;
+                    line.home_runs += 1;
+                }
+                Play::Flyout => line.fly_outs += 1,
+                Play::GroundOut => line.ground_outs += 1,
+                Play::FieldersChoice => {}
+                _ => continue,
+            }
+            line.plate_appearances += 1;
+            if event.play != Play::DoublePlay {
+                line.runs_batted_in += u32::from(runs);
+            }
+        }
+        if let Some(last) = feed.events.last() {
+            result.score = Score {
+                inning: last.after.inning,
+                bottom: last.after.bottom,
+                score: last
+                    .after
+                    .score
+                    .map(|runs| u8::try_from(*runs).unwrap_or(u8::MAX)),
+            };
+        }
+        result
+    }
+
+    fn hitting<T>(x: &mut AwayHome<T>, bottom: bool) -> &mut T {
+        if bottom {
+            &mut x.home
+        } else {
+            &mut x.away
+        }
+    }
+
+    fn fielding<T>(x: &mut AwayHome<T>, bottom: bool) -> &mut T {
+        GameResult::hitting(x, !bottom)
+    }
+
+    fn batting_line(&mut self, bottom: bool, player: &Player) -> Option<&mut BattingLine> {
+        GameResult::hitting(&mut self.batting, bottom)
+            .iter_mut()
+            .find(|line| line.id == player.id)
+    }
+}
+
+impl GameObserver for GameResult {
+    fn observe(&mut self, event: &GameEvent<'_>, score: &Score) {
+        let bottom = score.bottom;
+        match *event {
+            GameEvent::HalfInningStart => GameResult::hitting(&mut self.linescore, bottom).push(0),
+            GameEvent::Pitch { .. } => {
+                GameResult::fielding(&mut self.pitching, bottom).pitches += 1;
+            }
+            GameEvent::Walk { batter } => {
+                if let Some(line) = self.batting_line(bottom, batter) {
+                    line.plate_appearances += 1;
+                    line.walks += 1;
+                }
+            }
+            GameEvent::Strikeout { batter, .. } => {
+                if let Some(line) = self.batting_line(bottom, batter) {
+                    line.plate_appearances += 1;
+                    line.strikeouts += 1;
+                }
+                GameResult::fielding(&mut self.pitching, bottom).strikeouts += 1;
+            }
+            GameEvent::Out { batter, fly } => {
+                if let Some(line) = self.batting_line(bottom, batter) {
+                    line.plate_appearances += 1;
+                    if fly {
+                        line.fly_outs += 1;
+                    } else {
+                        line.ground_outs += 1;
+                    }
+                }
+            }
+            GameEvent::FieldersChoice { batter, .. } => {
+                if let Some(line) = self.batting_line(bottom, batter) {
+                    line.plate_appearances += 1;
+                }
+            }
+            GameEvent::DoublePlay { batter, .. } => {
+                if let Some(line) = self.batting_line(bottom, batter) {
+                    line.plate_appearances += 1;
+                    line.double_plays += 1;
+                }
+            }
+            GameEvent::Hit { batter, bases } => {
+                if let Some(line) = self.batting_line(bottom, batter) {
+                    line.plate_appearances += 1;
+                    line.hits += 1;
+                    match bases {
+                        2 => line.doubles += 1,
+                        3 => line.triples += 1,
+                        4 => line.home_runs += 1,
+                        _ => {}
+                    }
+                }
+            }
+            GameEvent::Run { runner, batter } => {
+                if let Some(runs) = GameResult::hitting(&mut self.linescore, bottom).last_mut() {
+                    *runs += 1;
+                }
+                if let Some(line) = self.batting_line(bottom, runner) {
+                    line.runs += 1;
+                }
+                if let Some(line) = batter.and_then(|batter| self.batting_line(bottom, batter)) {
+                    line.runs_batted_in += 1;
+                }
+                GameResult::fielding(&mut self.pitching, bottom).earned_runs += 1;
+            }
+            GameEvent::Sun2 => *GameResult::hitting(&mut self.sun_2, bottom) += 10,
+            GameEvent::StolenBase { runner, .. } => {
+                if let Some(line) = self.batting_line(bottom, runner) {
+                    line.stolen_bases += 1;
+                }
+            }
+            GameEvent::CaughtStealing { runner, .. } => {
+                if let Some(line) = self.batting_line(bottom, runner) {
+                    line.caught_stealing += 1;
+                }
+            }
+            GameEvent::Incineration {
+                victim,
+                replacement: player,
+            }
+            | GameEvent::Feedback {
+                batter: victim,
+                pitcher: player,
+            } => {
+                // the newcomer takes the place of the batter, if it was the batter who left
+                if self.batting_line(bottom, victim).is_some()
+                    && self.batting_line(bottom, player).is_none()
+                {
+                    GameResult::hitting(&mut self.batting, bottom).push(BattingLine::new(player));
+                }
+            }
+            GameEvent::Advance { .. }
+            | GameEvent::PeanutReaction { .. }
+            | GameEvent::Reverb { .. }
+            | GameEvent::Blooddrain { .. }
+            | GameEvent::GameOver => {}
+        }
+    }
+}
+
+impl BattingLine {
+    fn new(player: &Player) -> BattingLine {
+        BattingLine {
+            id: player.id,
+            name: player.name.clone(),
+            ..BattingLine::default()
+        }
+    }
+
+    /// Adds `other`'s stats to this line's.
+    pub fn add(&mut self, other: &BattingLine) {
+        self.plate_appearances += other.plate_appearances;
+        self.hits += other.hits;
+        self.doubles += other.doubles;
+        self.triples += other.triples;
+        self.home_runs += other.home_runs;
+        self.walks += other.walks;
+        self.strikeouts += other.strikeouts;
+        self.fly_outs += other.fly_outs;
+        self.ground_outs += other.ground_outs;
+        self.double_plays += other.double_plays;
+        self.runs_batted_in += other.runs_batted_in;
+        self.runs += other.runs;
+        self.stolen_bases += other.stolen_bases;
+        self.caught_stealing += other.caught_stealing;
+    }
+}
+
+impl PitchingLine {
+    fn new(player: &Player) -> PitchingLine {
+        PitchingLine {
+            id: player.id,
+            name: player.name.clone(),
+            ..PitchingLine::default()
+        }
+    }
+
+    /// Adds `other`'s stats to this line's.
+    pub fn add(&mut self, other: &PitchingLine) {
+        self.pitches += other.pitches;
+        self.strikeouts += other.strikeouts;
+        self.earned_runs += other.earned_runs;
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// Every player's stats summed over many simulations of a game, from [`project`]. Divide by
+/// `simulations` for per-game projections. Only the players in the game's lineups are counted,
+/// not the ones who join mid-game through the weather.
+#[derive(Debug, Serialize)]
+pub struct Projection {
+    pub simulations: u32,
+    /// Runs scored by each team, from the final scores, so counting everyone's.
+    pub runs: AwayHome<u32>,
+    /// Runs the Sun 2 took off each team's score; see [`GameResult::sun_2`].
+    pub sun_2: AwayHome<u32>,
+    pub batting: AwayHome<Vec<BattingLine>>,
+    pub pitching: AwayHome<PitchingLine>,
+}
+
+impl Projection {
+    fn merge(mut self, other: &Projection) -> Projection {
+        for (totals, lines) in [
+            (&mut self.batting.away, &other.batting.away),
+            (&mut self.batting.home, &other.batting.home),
+        ] {
+            for (total, line) in totals.iter_mut().zip(lines) {
+                total.add(line);
+            }
+        }
+        self.pitching.away.add(&other.pitching.away);
+        self.pitching.home.add(&other.pitching.home);
+        self.runs.away += other.runs.away;
+        self.runs.home += other.runs.home;
+        self.sun_2.away += other.sun_2.away;
+        self.sun_2.home += other.sun_2.home;
+        self.simulations += other.simulations;
+        self
+    }
+}
+
+impl From<GameResult> for Projection {
+    fn from(mut result: GameResult) -> Projection {
+        result.batting.away.truncate(9);
+        result.batting.home.truncate(9);
+        Projection {
+            simulations: 1,
+            runs: result.score.score.map(|runs| u32::from(*runs)),
+            sun_2: result.sun_2,
+            batting: result.batting,
+            pitching: result.pitching,
+        }
+    }
+}
+
+/// Simulates `game` `simulations` times, like [`monte_carlo_with`], and totals every player's
+/// stats.
+///
+/// [`monte_carlo_with`]: crate::monte_carlo_with
+pub fn project<M: PitchModel + Sync>(
+    game: &Playable,
+    model: &M,
+    params: &ModelParams,
+    simulations: u32,
+) -> Projection {
+    let empty = || Projection {
+        simulations: 0,
+        ..Projection::from(GameResult::new(game))
+    };
+    (0..simulations)
+        .into_par_iter()
+        .map(|i| Projection::from(game.simulate_with_result(model, params, u64::from(i))))
+        .reduce(empty, |a, b| a.merge(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{project, GameResult};
+    use crate::feed::{parse_feed, test_update};
+    use crate::game::test_game;
+    use crate::params::ModelParams;
+    use crate::pitch::DefaultModel;
+    use crate::util::AwayHome;
+    use crate::weather::Weather;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_box_score_adds_up() {
+        let game = test_game();
+        let params = ModelParams::default();
+        for seed in 0..20 {
+            let result = game.simulate_with_result(&DefaultModel, &params, seed);
+            let score = game.simulate_with(&DefaultModel, &params, seed);
+            assert_eq!(result.score.score.away, score.score.away);
+            assert_eq!(result.score.score.home, score.score.home);
+
+            for (runs, linescore, batting, pitching) in [
+                (
+                    score.score.away,
+                    &result.linescore.away,
+                    &result.batting.away,
+                    &result.pitching.home,
+                ),
+                (
+                    score.score.home,
+                    &result.linescore.home,
+                    &result.batting.home,
+                    &result.pitching.away,
+                ),
+            ] {
+                let runs = u32::from(runs);
+                assert_eq!(linescore.iter().map(|r| u32::from(*r)).sum::<u32>(), runs);
+                assert_eq!(batting.iter().map(|line| line.runs).sum::<u32>(), runs);
+                // runs scored on a double play or a stolen home aren't batted in
+                assert!(batting.iter().map(|line| line.runs_batted_in).sum::<u32>() <= runs);
+                assert_eq!(pitching.earned_runs, runs);
+                assert!(batting
+                    .iter()
+                    .all(|line| line.hits >= line.doubles + line.triples + line.home_runs));
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_feed() {
+        let mut game = test_game();
+        // the pitcher in every test update
+        game.pitchers.home.id = Uuid::nil();
+        let id = Uuid::from_u128;
+        let updates = [
+            test_update("", json!({})),
+            test_update("Top of 1, Tigers batting.", json!({})),
+            test_update(
+                "Batter 0 batting for the Tigers.",
+                json!({"awayBatter": id(0), "awayTeamBatterCount": 0}),
+            ),
+            test_update(
+                "Batter 0 hits a solo home run!",
+                json!({"awayTeamBatterCount": 0, "awayScore": 1}),
+            ),
+            test_update(
+                "Batter 1 batting for the Tigers.",
+                json!({"awayBatter": id(1), "awayTeamBatterCount": 1, "awayScore": 1}),
+            ),
+            test_update(
+                "Strike, swinging. 0-1",
+                json!({"awayBatter": id(1), "awayTeamBatterCount": 1, "awayScore": 1, "atBatStrikes": 1}),
+            ),
+            test_update(
+                "Batter 1 hit into a double play!",
+                json!({"awayTeamBatterCount": 1, "awayScore": 1, "halfInningOuts": 2}),
+            ),
+        ];
+        let result = GameResult::from_feed(&game, &parse_feed(&updates));
+
+        let batting = &result.batting.away;
+        assert_eq!(batting[0].plate_appearances, 1);
+        assert_eq!(batting[0].home_runs, 1);
+        assert_eq!(batting[0].runs_batted_in, 1);
+        assert_eq!(batting[1].plate_appearances, 1);
+        assert_eq!(batting[1].double_plays, 1);
+        assert_eq!(result.pitching.home.pitches, 3);
+        assert_eq!(result.pitching.home.earned_runs, 1);
+        assert_eq!(result.linescore.away, [1]);
+        assert_eq!(result.score.score.away, 1);
+    }
+
+    #[test]
+    fn test_project() {
+        let game = test_game();
+        let params = ModelParams::default();
+        let projection = project(&game, &DefaultModel, &params, 50);
+        assert_eq!(projection.simulations, 50);
+        let strikeouts: u32 = projection.batting.away.iter().map(|l| l.strikeouts).sum();
+        assert_eq!(strikeouts, projection.pitching.home.strikeouts);
+        assert_eq!(projection.runs.away, projection.pitching.home.earned_runs);
+        assert_eq!(projection.runs.home, projection.pitching.away.earned_runs);
+
+        // under the Sun 2, the final scores are short the runs it took
+        let mut game = test_game();
+        game.weather = Weather::Sun2;
+        game.pitchers = AwayHome::default();
+        let projection = project(&game, &DefaultModel, &params, 20);
+        assert!(projection.sun_2.away + projection.sun_2.home > 0);
+        assert_eq!(
+            projection.runs.away + projection.sun_2.away,
+            projection.pitching.home.earned_runs
+        );
+        assert_eq!(
+            projection.runs.home + projection.sun_2.home,
+            projection.pitching.away.earned_runs
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use crate::cmd::{DatabaseOpts, Filter, GameOpts, ModelOpts, OutputOpts, SimOpts};
+use anyhow::Result;
+use metasim::{bootstrap, Accuracy, Bootstrap, ModelParams, Paired};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct Backtest {
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    games: GameOpts,
+    #[structopt(flatten)]
+    filter: Filter,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    #[structopt(flatten)]
+    sim: SimOpts,
+    #[structopt(flatten)]
+    output: OutputOpts,
+    /// Resample the games this many times to put confidence intervals on the results
+    #[structopt(long, value_name = "resamples")]
+    bootstrap: Option<usize>,
+    /// Confidence level of the bootstrap intervals
+    #[structopt(long, default_value = "0.95")]
+    confidence: f64,
+    /// Seed for the bootstrap resampling
+    #[structopt(long, default_value = "0")]
+    seed: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    #[serde(flatten)]
+    overall: Comparison,
+    /// Games without a final score or official odds, like ones still in progress.
+    skipped_games: usize,
+    seasons: BTreeMap<u16, Comparison>,
+    regular_season: Comparison,
+    postseason: Comparison,
+    /// Split by which team the official odds favored.
+    away_favored: Comparison,
+    home_favored: Comparison,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bootstrap: Option<Bootstrap>,
+    params: ModelParams,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Comparison {
+    official: Accuracy,
+    ours: Accuracy,
+}
+
+impl Comparison {
+    fn record(&mut self, official: f64, ours: Option<f64>, actual: u8) {
+        self.official.record(official, actual);
+        if let Some(ours) = ours {
+            self.ours.record(ours, actual);
+        }
+    }
+
+    fn print(&self) {
+        println!("official: {}", self.official);
+        println!("    ours: {}", self.ours);
+    }
+}
+
+impl Backtest {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+
+        let mut report = Report {
+            overall: Comparison::default(),
+            skipped_games: 0,
+            seasons: BTreeMap::new(),
+            regular_season: Comparison::default(),
+            postseason: Comparison::default(),
+            away_favored: Comparison::default(),
+            home_favored: Comparison::default(),
+            bootstrap: None,
+            params: self.model.load()?,
+        };
+        let mut paired = Vec::new();
+        for game in self.games.load(&self.filter)? {
+            let (Ok(away_won), Ok(official)) = (game.away_won(), game.official_odds()) else {
+                report.skipped_games += 1;
+                continue;
+            };
+            let actual = u8::from(away_won);
+            let ours = game
+                .playable(&database)
+                .map(|playable| self.sim.away_odds(&playable, &report.params))
+                .transpose()?;
+
+            let splits = [
+                &mut report.overall,
+                report.seasons.entry(game.season + 1).or_default(),
+                if game.is_postseason {
+                    &mut report.postseason
+                } else {
+                    &mut report.regular_season
+                },
+                if official > 0.5 {
+                    &mut report.away_favored
+                } else {
+                    &mut report.home_favored
+                },
+            ];
+            for split in splits {
+                split.record(official, ours, actual);
+            }
+            if let Some(ours) = ours {
+                paired.push(Paired {
+                    baseline: official,
+                    ours,
+                    actual,
+                });
+            }
+        }
+        if let Some(resamples) = self.bootstrap {
+            report.bootstrap = Some(bootstrap(&paired, resamples, self.confidence, self.seed));
+        }
+
+        self.output.print(&report, Report::print)
+    }
+}
+
+impl Report {
+    fn print(&self) {
+        println!(
+            "{} games ({} skipped)",
+            self.overall.official.len(),
+            self.skipped_games
+        );
+        self.overall.print();
+
+        if let Some(bootstrap) = &self.bootstrap {
+            println!();
+            println!(
+                "bootstrap ({} resamples of games we could simulate, {}% intervals):",
+                bootstrap.resamples,
+                bootstrap.confidence * 100.0
+            );
+            println!("official: {}", bootstrap.baseline);
+            println!("    ours: {}", bootstrap.ours);
+            println!("ours - official: {}", bootstrap.difference);
+        }
+
+        println!();
+        println!("brier score decomposition:");
+        for (name, accuracy) in &[
+            ("official", &self.overall.official),
+            ("ours", &self.overall.ours),
+        ] {
+            println!(
+                "{:>8}: reliability: {:.4}, resolution: {:.4}, uncertainty: {:.4}",
+                name,
+                accuracy.reliability(),
+                accuracy.resolution(),
+                accuracy.uncertainty()
+            );
+        }
+
+        for (name, accuracy) in &[
+            ("official", &self.overall.official),
+            ("ours", &self.overall.ours),
+        ] {
+            println!();
+            println!("calibration ({name}):");
+            println!("    odds      games  expected  actual");
+            for bucket in accuracy.calibration() {
+                println!(
+                    "    {:.1}-{:.1}  {:>7}  {:>8.3}  {:>6.3}",
+                    bucket.min, bucket.max, bucket.games, bucket.expected, bucket.actual
+                );
+            }
+        }
+
+        let mut splits = self
+            .seasons
+            .iter()
+            .map(|(season, split)| (format!("season {season}"), split))
+            .collect::<Vec<_>>();
+        splits.extend(vec![
+            ("regular season".to_owned(), &self.regular_season),
+            ("postseason".to_owned(), &self.postseason),
+            ("away favored".to_owned(), &self.away_favored),
+            ("home favored".to_owned(), &self.home_favored),
+        ]);
+        for (name, split) in splits {
+            if !split.official.is_empty() {
+                println!();
+                println!("{name} ({} games):", split.official.len());
+                split.print();
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use crate::cmd::{load_updates, positive, DatabaseOpts, Filter, ModelOpts};
+use anyhow::{anyhow, bail, Error, Result};
+use metasim::{score_distribution_from, DefaultModel, Game, ScoreDistribution};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use structopt::StructOpt;
+use uuid::Uuid;
+
+#[derive(Debug, StructOpt)]
+pub struct Chart {
+    /// JSON file of one game's updates in order: an array, one update per line, or a Chronicler
+    /// page whose `data` holds them; or a directory of them
+    #[structopt(parse(from_os_str))]
+    updates: PathBuf,
+    /// Only chart this game, if the file has updates for more than one
+    #[structopt(long)]
+    game: Option<Uuid>,
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    /// Number of simulations to run per update
+    #[structopt(short = "n", long, default_value = "1000", parse(try_from_str = positive))]
+    simulations: u32,
+    /// Width of the sparkline, in characters
+    #[structopt(long, default_value = "80")]
+    width: usize,
+    /// Output format
+    #[structopt(long, default_value = "text", possible_values = &["text", "json", "csv"])]
+    format: SeriesFormat,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SeriesFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for SeriesFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SeriesFormat> {
+        match s {
+            "text" => Ok(SeriesFormat::Text),
+            "json" => Ok(SeriesFormat::Json),
+            "csv" => Ok(SeriesFormat::Csv),
+            _ => bail!("unknown output format {:?}", s),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Series {
+    id: Uuid,
+    away_team: String,
+    home_team: String,
+    simulations: u32,
+    points: Vec<Point>,
+}
+
+/// Our win probability after one update.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Point {
+    update: usize,
+    /// Numbered from 1, as displayed on the site.
+    inning: u8,
+    top: bool,
+    outs: u8,
+    balls: u8,
+    strikes: u8,
+    away_score: u16,
+    home_score: u16,
+    /// `None` if the update couldn't be simulated from, e.g. a runner isn't in the lineup.
+    away_odds: Option<f64>,
+    last_update: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Chart {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let params = self.model.load()?;
+        let updates = load_game_updates(&self.updates, self.game)?;
+        let first = updates
+            .first()
+            .ok_or_else(|| anyhow!("no updates in {}", self.updates.display()))?;
+        let playable = first
+            .playable(&database)
+            .ok_or_else(|| anyhow!("unable to resolve rosters for game {}", first.id))?;
+
+        let points = updates
+            .iter()
+            .enumerate()
+            .map(|(i, update)| {
+                let snapshot = &update.snapshot;
+                let distribution = score_distribution_from(
+                    &playable,
+                    snapshot,
+                    &DefaultModel,
+                    &params,
+                    self.simulations,
+                );
+                Point {
+                    update: i,
+                    inning: snapshot.inning + 1,
+                    top: snapshot.top_of_inning,
+                    outs: snapshot.half_inning_outs,
+                    balls: snapshot.at_bat_balls,
+                    strikes: snapshot.at_bat_strikes,
+                    away_score: snapshot.score().away,
+                    home_score: snapshot.score().home,
+                    away_odds: distribution.as_ref().ok().map(ScoreDistribution::away_odds),
+                    last_update: snapshot.last_update.clone(),
+                    error: distribution.err().map(|err| err.to_string()),
+                }
+            })
+            .collect();
+        let series = Series {
+            id: first.id,
+            away_team: database.team_name(first.away_team, first.timestamp()),
+            home_team: database.team_name(first.home_team, first.timestamp()),
+            simulations: self.simulations,
+            points,
+        };
+
+        match self.format {
+            SeriesFormat::Text => print_text(&series, self.width),
+            SeriesFormat::Json => println!("{}", serde_json::to_string_pretty(&series)?),
+            SeriesFormat::Csv => print_csv(&series),
+        }
+        Ok(())
+    }
+}
+
+/// Reads the updates of one game from `path`, which must only have one unless `game` is given.
+fn load_game_updates(path: &Path, game: Option<Uuid>) -> Result<Vec<Game>> {
+    let mut updates = load_updates(path, &Filter::default())?;
+    let id = match game {
+        Some(id) => id,
+        None => match updates.first() {
+            Some(update) => update.id,
+            None => return Ok(updates),
+        },
+    };
+    if game.is_none() && updates.iter().any(|update| update.id != id) {
+        bail!(
+            "{} has updates for more than one game; pick one with --game",
+            path.display()
+        );
+    }
+    updates.retain(|update| update.id == id);
+    Ok(updates)
+}
+
+fn print_text(series: &Series, width: usize) {
+    println!("{} @ {}", series.away_team, series.home_team);
+    let odds = series
+        .points
+        .iter()
+        .map(|p| p.away_odds)
+        .collect::<Vec<_>>();
+    let (line, columns) = sparkline(&odds, width);
+
+    // mark the top of each inning under the sparkline
+    let mut innings = vec![' '; line.chars().count()];
+    let mut last_inning = 0;
+    for (point, column) in series.points.iter().zip(&columns) {
+        if point.inning != last_inning && point.top {
+            last_inning = point.inning;
+            innings[*column] = std::char::from_digit(u32::from(point.inning % 10), 10).unwrap();
+        }
+    }
+    println!("away {line}");
+    println!("     {}", innings.into_iter().collect::<String>());
+
+    let skipped = series.points.iter().filter(|p| p.error.is_some()).count();
+    if let Some(point) = series.points.iter().find(|p| p.error.is_some()) {
+        println!(
+            "skipped {} of {} updates, e.g. update {}: {}",
+            skipped,
+            series.points.len(),
+            point.update,
+            point.error.as_deref().unwrap_or_default()
+        );
+    }
+
+    let mut swings = Vec::new();
+    let mut previous = None;
+    for point in &series.points {
+        if let Some(odds) = point.away_odds {
+            if let Some(previous) = previous {
+                swings.push((odds - previous, point));
+            }
+            previous = Some(odds);
+        }
+    }
+    swings.sort_by(|a, b| b.0.abs().total_cmp(&a.0.abs()));
+    println!();
+    println!("biggest swings in the away team's win probability:");
+    for (swing, point) in swings.iter().take(5) {
+        println!(
+            "{:+.3} {} {}, {} out, {}-{}, {}-{}: {}",
+            swing,
+            if point.top { "top" } else { "bottom" },
+            point.inning,
+            point.outs,
+            point.balls,
+            point.strikes,
+            point.away_score,
+            point.home_score,
+            point.last_update
+        );
+    }
+}
+
+fn print_csv(series: &Series) {
+    println!("update,inning,top,outs,balls,strikes,away_score,home_score,away_odds,last_update");
+    for p in &series.points {
+        println!(
+            "{},{},{},{},{},{},{},{},{},\"{}\"",
+            p.update,
+            p.inning,
+            p.top,
+            p.outs,
+            p.balls,
+            p.strikes,
+            p.away_score,
+            p.home_score,
+            p.away_odds.map(|odds| odds.to_string()).unwrap_or_default(),
+            p.last_update.replace('"', "\"\"")
+        );
+    }
+}
+
+/// Draws `values` (from 0 to 1) as a line of block characters at most `width` wide, taking the
+/// last value in each column when there are more values than columns. Also returns the column
+/// each value landed in. Missing values are blank.
+fn sparkline(values: &[Option<f64>], width: usize) -> (String, Vec<usize>) {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let width = width.max(1).min(values.len());
+    let columns = (0..values.len())
+        .map(|i| i * width / values.len())
+        .collect::<Vec<_>>();
+    let mut line = vec![' '; width];
+    for (value, column) in values.iter().zip(&columns) {
+        line[*column] = match value {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            Some(p) => BARS[((p * 8.0) as usize).min(7)],
+            None => ' ',
+        };
+    }
+    (line.into_iter().collect(), columns)
+}
@@ -0,0 +1,166 @@
+use crate::cmd::{positive, DatabaseOpts, OutputOpts};
+use anyhow::{anyhow, Result};
+use metasim::{game_time, Database, Player};
+use serde::Serialize;
+use structopt::StructOpt;
+use uuid::Uuid;
+
+#[derive(Debug, StructOpt)]
+pub struct Db {
+    #[structopt(subcommand)]
+    command: DbCommand,
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    output: OutputOpts,
+}
+
+#[derive(Debug, StructOpt)]
+enum DbCommand {
+    /// Build the database cache and print a summary
+    Summary,
+    /// Show a team's lineup and rotation
+    Team {
+        id: Uuid,
+        #[structopt(flatten)]
+        time: TimeOpts,
+    },
+    /// Show a player's attributes
+    Player {
+        id: Uuid,
+        #[structopt(flatten)]
+        time: TimeOpts,
+    },
+}
+
+/// Defaults to the latest record in the database.
+#[derive(Debug, StructOpt)]
+struct TimeOpts {
+    /// Season to look up, starting from 1
+    #[structopt(long, requires = "day", parse(try_from_str = positive))]
+    season: Option<u16>,
+    /// Day to look up, starting from 1; also used for the player's vibes
+    #[structopt(long, requires = "season", parse(try_from_str = positive))]
+    day: Option<u8>,
+}
+
+impl TimeOpts {
+    fn timestamp(&self) -> u64 {
+        match (self.season, self.day) {
+            (Some(season), Some(day)) => game_time(season - 1, day - 1),
+            _ => u64::MAX,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    teams: usize,
+    players: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Roster<'a> {
+    id: Uuid,
+    nickname: &'a str,
+    lineup: Vec<String>,
+    rotation: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Ratings {
+    #[serde(flatten)]
+    player: Player,
+    batting: f64,
+    pitching: f64,
+    baserunning: f64,
+    defense: f64,
+}
+
+impl Db {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        match &self.command {
+            DbCommand::Summary => {
+                let summary = Summary {
+                    teams: database.teams.len(),
+                    players: database.players.len(),
+                };
+                self.output.print(&summary, |s| {
+                    println!("{} teams, {} players", s.teams, s.players);
+                })
+            }
+            DbCommand::Team { id, time } => {
+                let timestamp = time.timestamp();
+                let team = database
+                    .teams
+                    .get(id)
+                    .and_then(|h| h.get(timestamp))
+                    .ok_or_else(|| anyhow!("team {} not found", id))?;
+                let names = |ids: &[Uuid]| {
+                    ids.iter()
+                        .map(|id| player_name(&database, *id, timestamp))
+                        .collect()
+                };
+                let roster = Roster {
+                    id: team.id,
+                    nickname: &team.nickname,
+                    lineup: names(&team.lineup),
+                    rotation: names(&team.rotation),
+                };
+                self.output.print(&roster, |r| {
+                    println!("{} ({})", r.nickname, r.id);
+                    println!("lineup: {}", r.lineup.join(", "));
+                    println!("rotation: {}", r.rotation.join(", "));
+                })
+            }
+            DbCommand::Player { id, time } => {
+                let mut player = database
+                    .players
+                    .get(id)
+                    .and_then(|h| h.get(time.timestamp()))
+                    .ok_or_else(|| anyhow!("player {} not found", id))?
+                    .clone();
+                if let Some(day) = time.day {
+                    player.vibe_check(day - 1);
+                }
+                let ratings = Ratings {
+                    batting: player.batting(),
+                    pitching: player.pitching(),
+                    baserunning: player.baserunning(),
+                    defense: player.defense(),
+                    player,
+                };
+                self.output.print(&ratings, |r| {
+                    println!("{} ({})", r.player.name, r.player.id);
+                    println!("batting: {:.3}", r.batting);
+                    println!("pitching: {:.3}", r.pitching);
+                    println!("baserunning: {:.3}", r.baserunning);
+                    println!("defense: {:.3}", r.defense);
+                    let player = &r.player;
+                    let mods = [
+                        &player.perm_attr[..],
+                        &player.seas_attr,
+                        &player.week_attr,
+                        &player.game_attr,
+                    ]
+                    .concat();
+                    if !mods.is_empty() {
+                        println!("mods: {}", mods.join(", "));
+                    }
+                    if player.peanut_allergy {
+                        println!("allergic to peanuts");
+                    }
+                })
+            }
+        }
+    }
+}
+
+fn player_name(database: &Database, id: Uuid, timestamp: u64) -> String {
+    database
+        .players
+        .get(&id)
+        .and_then(|h| h.get(timestamp))
+        .map_or_else(|| id.to_string(), |player| player.name.clone())
+}
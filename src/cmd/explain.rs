@@ -0,0 +1,38 @@
+use crate::cmd::{DatabaseOpts, GameOpts, ModelOpts, OutputOpts};
+use anyhow::{anyhow, Result};
+use metasim::DefaultModel;
+use structopt::StructOpt;
+use uuid::Uuid;
+
+/// Run with `RUST_LOG` to narrow the trace output, e.g. `RUST_LOG=metasim::pitch=trace`.
+#[derive(Debug, StructOpt)]
+pub struct Explain {
+    /// ID of the game to simulate
+    game: Uuid,
+    /// Seed for the simulation
+    #[structopt(long, default_value = "0")]
+    seed: u64,
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    games: GameOpts,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    #[structopt(flatten)]
+    output: OutputOpts,
+}
+
+impl Explain {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let game = self.games.find(self.game)?;
+        let playable = game
+            .playable(&database)
+            .ok_or_else(|| anyhow!("unable to resolve rosters for game {}", game.id))?;
+
+        let score = playable.simulate_with(&DefaultModel, &self.model.load()?, self.seed);
+        self.output.print(&score, |score| {
+            println!("final score: {}-{}", score.score.away, score.score.home);
+        })
+    }
+}
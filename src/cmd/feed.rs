@@ -0,0 +1,74 @@
+use crate::cmd::{group_updates, load_updates, Filter, OutputOpts};
+use anyhow::Result;
+use metasim::{parse_feed, PlayEvent, Unparsed};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use uuid::Uuid;
+
+#[derive(Debug, StructOpt)]
+pub struct Feed {
+    /// JSON file or directory of game updates, in order; see `chart` for the formats read
+    #[structopt(parse(from_os_str))]
+    updates: PathBuf,
+    #[structopt(flatten)]
+    filter: Filter,
+    #[structopt(flatten)]
+    output: OutputOpts,
+}
+
+#[derive(Debug, Serialize)]
+struct GameFeed {
+    id: Uuid,
+    events: Vec<PlayEvent>,
+    unparsed: Vec<Unparsed>,
+}
+
+impl Feed {
+    pub fn run(self) -> Result<()> {
+        let games = group_updates(load_updates(&self.updates, &self.filter)?);
+        let updates = games.iter().map(Vec::len).sum::<usize>();
+        let feeds = games
+            .iter()
+            .map(|updates| {
+                let feed = parse_feed(updates);
+                GameFeed {
+                    id: updates[0].id,
+                    events: feed.events,
+                    unparsed: feed.unparsed,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.output.print(&feeds, |feeds| {
+            let mut plays = BTreeMap::new();
+            for event in feeds.iter().flat_map(|feed| &feed.events) {
+                *plays.entry(format!("{:?}", event.play)).or_insert(0) += 1;
+            }
+            let events = feeds.iter().map(|feed| feed.events.len()).sum::<usize>();
+            let unparsed = feeds.iter().map(|feed| feed.unparsed.len()).sum::<usize>();
+            println!(
+                "{} games, {} updates: {} events, {} lines not understood",
+                feeds.len(),
+                updates,
+                events,
+                unparsed
+            );
+            if !plays.is_empty() {
+                println!();
+                for (play, count) in &plays {
+                    println!("{count:>7}  {play}");
+                }
+            }
+            if unparsed > 0 {
+                println!();
+                for feed in feeds {
+                    for line in &feed.unparsed {
+                        println!("{} update {}: {}", feed.id, line.update, line.text);
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use crate::cmd::{load_feeds, DatabaseOpts, Filter, GameOpts, ModelOpts, SimOpts};
+use anyhow::{bail, Error, Result};
+use metasim::fit::nelder_mead;
+use metasim::{log_likelihood, observed_pitches, Accuracy, DefaultModel, ModelParams, Playable};
+use rayon::prelude::*;
+use std::path::PathBuf;
+use std::str::FromStr;
+use structopt::StructOpt;
+use tracing::info;
+
+/// The objective over game results is the Brier score or log loss of the predicted winners. Every
+/// simulation count uses the same seeds, so the objective only changes when the parameters do;
+/// the `markov` engine has no sampling noise at all. With `--updates`, the objective is the mean
+/// negative log-likelihood of every pitch in the archived games instead, which needs no
+/// simulation. Progress is logged at the info level.
+#[derive(Debug, StructOpt)]
+pub struct Fit {
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    games: GameOpts,
+    #[structopt(flatten)]
+    filter: Filter,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    #[structopt(flatten)]
+    sim: SimOpts,
+    /// What to minimize over game results
+    #[structopt(long, default_value = "brier", possible_values = &["brier", "log-loss"])]
+    objective: Objective,
+    /// Fit to the pitches in this JSON file or directory of game updates instead of to game
+    /// results; see `chart` for the formats read
+    #[structopt(long, parse(from_os_str))]
+    updates: Option<PathBuf>,
+    /// Only fit coefficients whose name starts with this, e.g. `contact` or `foul` (may be
+    /// repeated; defaults to all)
+    #[structopt(long = "only", number_of_values = 1)]
+    only: Vec<String>,
+    /// Initial step size for each coefficient
+    #[structopt(long, default_value = "0.02")]
+    step: f64,
+    /// Stop after evaluating the objective this many times
+    #[structopt(long, default_value = "200")]
+    max_evaluations: usize,
+    /// Stop once the objective is this close across the search simplex
+    #[structopt(long, default_value = "1e-6")]
+    tolerance: f64,
+    /// Write the fitted parameters to this .json or .toml file instead of printing them
+    #[structopt(long, parse(from_os_str))]
+    out: Option<PathBuf>,
+}
+
+impl Fit {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let start = self.model.load()?;
+
+        let feeds = match &self.updates {
+            Some(path) => load_feeds(path, &self.filter, &database)?.0,
+            None => Vec::new(),
+        };
+        let pitches = feeds
+            .iter()
+            .flat_map(|(game, feed)| observed_pitches(game, feed).0)
+            .collect::<Vec<_>>();
+        let mut games: Vec<(Playable, u8)> = Vec::new();
+        // games without a final score, like ones still in progress
+        let mut skipped = 0;
+        if self.updates.is_none() {
+            for game in self.games.load(&self.filter)? {
+                let Ok(away_won) = game.away_won() else {
+                    skipped += 1;
+                    continue;
+                };
+                if let Some(playable) = game.playable(&database) {
+                    games.push((playable, u8::from(away_won)));
+                }
+            }
+        }
+        if games.is_empty() && pitches.is_empty() {
+            bail!("no playable games or pitches to fit against");
+        }
+        // lower is better for both
+        let error = |params: &ModelParams| -> Result<f64> {
+            if self.updates.is_some() {
+                Ok(-log_likelihood(&DefaultModel, params, &pitches).per_pitch())
+            } else {
+                let accuracy = accuracy(&games, params, &self.sim)?;
+                Ok(match self.objective {
+                    Objective::Brier => accuracy.error(),
+                    Objective::LogLoss => accuracy.log_loss(),
+                })
+            }
+        };
+
+        let (names, x0): (Vec<String>, Vec<f64>) = start
+            .coefficients()
+            .into_iter()
+            .filter(|(name, _)| {
+                self.only.is_empty() || self.only.iter().any(|prefix| name.starts_with(prefix))
+            })
+            .unzip();
+        if names.is_empty() {
+            bail!("no coefficients match {:?}", self.only);
+        }
+        info!(
+            games = games.len() + feeds.len(),
+            skipped,
+            pitches = pitches.len(),
+            coefficients = ?names,
+            "fitting"
+        );
+
+        let params = |x: &[f64]| {
+            start.with_coefficients(names.iter().map(String::as_str).zip(x.iter().copied()))
+        };
+        let mut best = f64::INFINITY;
+        let objective = |x: &[f64]| {
+            let params = match params(x) {
+                Ok(params) if params.is_valid() => params,
+                _ => return f64::INFINITY,
+            };
+            // the starting error below already failed if the games can't be solved at all
+            let Ok(error) = error(&params) else {
+                return f64::INFINITY;
+            };
+            if error < best {
+                best = error;
+                info!(%error, ?x, "improved");
+            }
+            error
+        };
+
+        let before = error(&start)?;
+        let min = nelder_mead(
+            objective,
+            &x0,
+            self.step,
+            self.max_evaluations,
+            self.tolerance,
+        );
+        info!(
+            %before,
+            after = %min.value,
+            evaluations = min.evaluations,
+            "done"
+        );
+
+        let fitted = params(&min.x)?;
+        match &self.out {
+            Some(path) => fitted.save(path)?,
+            None => print!("{}", fitted.to_toml()?),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Objective {
+    Brier,
+    LogLoss,
+}
+
+impl FromStr for Objective {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Objective> {
+        match s {
+            "brier" => Ok(Objective::Brier),
+            "log-loss" => Ok(Objective::LogLoss),
+            _ => bail!("unknown objective {:?}", s),
+        }
+    }
+}
+
+fn accuracy(games: &[(Playable, u8)], params: &ModelParams, sim: &SimOpts) -> Result<Accuracy> {
+    let predictions = games
+        .par_iter()
+        .map(|(game, _)| sim.away_odds(game, params))
+        .collect::<Result<Vec<_>>>()?;
+    let mut accuracy = Accuracy::default();
+    for (expected, (_, actual)) in predictions.into_iter().zip(games) {
+        accuracy.record(expected, *actual);
+    }
+    Ok(accuracy)
+}
@@ -0,0 +1,80 @@
+use crate::cmd::{load_feeds, DatabaseOpts, Filter, ModelOpts, OutputOpts};
+use anyhow::{bail, Result};
+use metasim::{log_likelihood, observed_pitches, DefaultModel, Pitch};
+use serde::Serialize;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct Likelihood {
+    /// JSON file or directory of game updates, in order; see `chart` for the formats read
+    #[structopt(parse(from_os_str))]
+    updates: PathBuf,
+    #[structopt(flatten)]
+    filter: Filter,
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    #[structopt(flatten)]
+    output: OutputOpts,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    games: usize,
+    skipped_games: usize,
+    /// Pitches whose batter or pitcher isn't in the game's lineups.
+    skipped_pitches: usize,
+    #[serde(flatten)]
+    likelihood: metasim::Likelihood,
+}
+
+impl Likelihood {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let params = self.model.load()?;
+        let (feeds, skipped_games) = load_feeds(&self.updates, &self.filter, &database)?;
+
+        let mut pitches = Vec::new();
+        let mut skipped_pitches = 0;
+        for (game, feed) in &feeds {
+            let (observed, skipped) = observed_pitches(game, feed);
+            pitches.extend(observed);
+            skipped_pitches += skipped;
+        }
+        if pitches.is_empty() {
+            bail!("no pitches found in {}", self.updates.display());
+        }
+
+        let report = Report {
+            games: feeds.len(),
+            skipped_games,
+            skipped_pitches,
+            likelihood: log_likelihood(&DefaultModel, &params, &pitches),
+        };
+        self.output.print(&report, |report| {
+            let likelihood = &report.likelihood;
+            println!(
+                "{} pitches in {} games ({} games and {} pitches skipped)",
+                likelihood.pitches, report.games, report.skipped_games, report.skipped_pitches
+            );
+            println!(
+                "log-likelihood {:.3}, {:.5} per pitch",
+                likelihood.log_likelihood,
+                likelihood.per_pitch()
+            );
+            println!();
+            println!("{:<8} {:>8} {:>10}", "", "observed", "expected");
+            for pitch in Pitch::ALL {
+                println!(
+                    "{:<8} {:>8} {:>10.1}",
+                    format!("{pitch:?}"),
+                    likelihood.observed[pitch.index()],
+                    likelihood.expected[pitch.index()]
+                );
+            }
+        })
+    }
+}
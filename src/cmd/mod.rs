@@ -0,0 +1,373 @@
+mod backtest;
+mod chart;
+mod db;
+mod explain;
+mod feed;
+mod fit;
+mod likelihood;
+mod narrate;
+mod predict;
+mod rates;
+mod replay;
+mod simulate;
+mod wpa;
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+use metasim::{
+    markov, monte_carlo_with, parse_feed, Database, DefaultModel, Feed, Game, ModelParams,
+    Playable, ScoreDistribution,
+};
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Once;
+use structopt::StructOpt;
+use tracing::warn;
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Blaseball simulator and backtester")]
+pub enum Command {
+    /// Compare the model's predictions with the results of completed games
+    Backtest(backtest::Backtest),
+    /// Print the model's win probabilities for a set of games
+    Predict(predict::Predict),
+    /// Simulate a single game many times and summarize the results
+    Simulate(simulate::Simulate),
+    /// Inspect the team and player database
+    Db(db::Db),
+    /// Trace a single simulation of a game pitch by pitch
+    Explain(explain::Explain),
+    /// Parse the play-by-play of recorded games into events, listing lines that aren't understood
+    Feed(feed::Feed),
+    /// Replay recorded games through the simulation's rules and report where each first differs
+    Replay(replay::Replay),
+    /// Compare simulated outcome rates, like strikeouts and walks per plate appearance, with
+    /// recorded games
+    Rates(rates::Rates),
+    /// Chart our win probability through the updates of a recorded game
+    Chart(chart::Chart),
+    /// Credit each player with the win probability they added over recorded games
+    Wpa(wpa::Wpa),
+    /// Play out a single simulation of a game as a Blaseball-style ticker
+    Narrate(narrate::Narrate),
+    /// Score the model by the log-likelihood of the pitches in recorded games
+    Likelihood(likelihood::Likelihood),
+    /// Search for model parameters that minimize the Brier score or log loss over completed games
+    Fit(fit::Fit),
+}
+
+impl Command {
+    /// The tracing filter used when `RUST_LOG` is unset.
+    pub fn default_filter(&self) -> &'static str {
+        match self {
+            Command::Explain(_) => "metasim=trace",
+            Command::Fit(_) => "metasim=info",
+            _ => "warn",
+        }
+    }
+
+    pub fn run(self) -> Result<()> {
+        match self {
+            Command::Backtest(cmd) => cmd.run(),
+            Command::Predict(cmd) => cmd.run(),
+            Command::Simulate(cmd) => cmd.run(),
+            Command::Db(cmd) => cmd.run(),
+            Command::Explain(cmd) => cmd.run(),
+            Command::Feed(cmd) => cmd.run(),
+            Command::Replay(cmd) => cmd.run(),
+            Command::Rates(cmd) => cmd.run(),
+            Command::Chart(cmd) => cmd.run(),
+            Command::Wpa(cmd) => cmd.run(),
+            Command::Narrate(cmd) => cmd.run(),
+            Command::Likelihood(cmd) => cmd.run(),
+            Command::Fit(cmd) => cmd.run(),
+        }
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+#[derive(Debug, StructOpt)]
+pub struct DatabaseOpts {
+    /// Directory of SIBR roster / player archives
+    #[structopt(long, default_value = "team-data", parse(from_os_str))]
+    team_data: PathBuf,
+}
+
+impl DatabaseOpts {
+    pub fn load(&self) -> Result<Database> {
+        Database::load(&self.team_data)
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct GameOpts {
+    /// File or directory of game-data JSON
+    #[structopt(long, default_value = "game-data", parse(from_os_str))]
+    game_data: PathBuf,
+}
+
+impl GameOpts {
+    pub fn load(&self, filter: &Filter) -> Result<Vec<Game>> {
+        load_games(&self.game_data, filter)
+    }
+
+    pub fn find(&self, id: Uuid) -> Result<Game> {
+        self.load(&Filter::default())?
+            .into_iter()
+            .find(|game| game.id == id)
+            .ok_or_else(|| anyhow!("game {} not found in {}", id, self.game_data.display()))
+    }
+}
+
+/// Reads every game matching `filter` from a JSON file, or a directory of them.
+pub fn load_games(path: &Path, filter: &Filter) -> Result<Vec<Game>> {
+    let mut games = Vec::new();
+    for entry in WalkDir::new(path).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let file: Vec<Game> = serde_json::from_reader(File::open(entry.path())?)
+                .with_context(|| format!("failed to parse {}", entry.path().display()))?;
+            games.extend(file.into_iter().filter(|game| filter.matches(game)));
+        }
+    }
+    Ok(games)
+}
+
+/// Reads every game update matching `filter` from a JSON file, or a directory of them, in order.
+/// A file can hold an array of updates, one update per line, or a Chronicler page whose `data`
+/// holds them, and each update may be wrapped in a Chronicler record whose `data` field holds it.
+pub fn load_updates(path: &Path, filter: &Filter) -> Result<Vec<Game>> {
+    let mut updates = Vec::new();
+    for entry in WalkDir::new(path).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        let s = fs::read_to_string(path)?;
+        let values = match serde_json::from_str(&s) {
+            Ok(Value::Array(values)) => values,
+            Ok(mut value) => match value.get_mut("data") {
+                Some(Value::Array(values)) => std::mem::take(values),
+                _ => vec![value],
+            },
+            Err(_) => s
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<serde_json::Result<_>>()
+                .with_context(|| format!("failed to parse {}", path.display()))?,
+        };
+
+        for (i, mut value) in values.into_iter().enumerate() {
+            if value.get("inning").is_none() {
+                if let Some(data) = value.get_mut("data") {
+                    value = data.take();
+                }
+            }
+            let update: Game = serde_json::from_value(value)
+                .with_context(|| format!("failed to parse update {} of {}", i, path.display()))?;
+            if filter.matches(&update) {
+                updates.push(update);
+            }
+        }
+    }
+    Ok(updates)
+}
+
+/// Splits updates into one list per game, in the order each game first appears.
+pub fn group_updates(updates: Vec<Game>) -> Vec<Vec<Game>> {
+    let mut games: Vec<Vec<Game>> = Vec::new();
+    let mut index: FxHashMap<Uuid, usize> = FxHashMap::default();
+    for update in updates {
+        let i = *index.entry(update.id).or_insert_with(|| {
+            games.push(Vec::new());
+            games.len() - 1
+        });
+        games[i].push(update);
+    }
+    games
+}
+
+/// Reads the updates of archived games and parses each game's feed, paired with its resolved
+/// rosters. Also returns how many games' rosters couldn't be resolved.
+pub fn load_feeds(
+    path: &Path,
+    filter: &Filter,
+    database: &Database,
+) -> Result<(Vec<(Playable, Feed)>, usize)> {
+    let mut feeds = Vec::new();
+    let mut skipped = 0;
+    for updates in group_updates(load_updates(path, filter)?) {
+        match updates[0].playable(database) {
+            Some(game) => feeds.push((game, parse_feed(&updates))),
+            None => skipped += 1,
+        }
+    }
+    Ok((feeds, skipped))
+}
+
+/// Seasons and days are numbered as displayed on the site, starting from 1.
+#[derive(Debug, Default, StructOpt)]
+pub struct Filter {
+    /// Only include games from this season, starting from 1 (may be repeated)
+    #[structopt(long = "season", number_of_values = 1, parse(try_from_str = positive))]
+    seasons: Vec<u16>,
+    /// Only include games from this day, starting from 1 (may be repeated)
+    #[structopt(long = "day", number_of_values = 1, parse(try_from_str = positive))]
+    days: Vec<u16>,
+}
+
+impl Filter {
+    fn matches(&self, game: &Game) -> bool {
+        (self.seasons.is_empty() || self.seasons.contains(&(game.season + 1)))
+            && (self.days.is_empty() || self.days.contains(&(u16::from(game.day) + 1)))
+    }
+}
+
+/// Parses a season, day or count, none of which can be 0.
+pub fn positive<T>(s: &str) -> Result<T>
+where
+    T: FromStr + PartialOrd + From<u8>,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let n: T = s.parse()?;
+    if n < T::from(1) {
+        bail!("must be at least 1");
+    }
+    Ok(n)
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ModelOpts {
+    /// JSON or TOML file of model parameters; unset fields keep their defaults
+    #[structopt(long, parse(from_os_str))]
+    params: Option<PathBuf>,
+}
+
+impl ModelOpts {
+    pub fn load(&self) -> Result<ModelParams> {
+        self.params
+            .as_ref()
+            .map_or_else(|| Ok(ModelParams::default()), ModelParams::load)
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SimOpts {
+    /// Number of simulations to run per game
+    #[structopt(short = "n", long, default_value = "1000", parse(try_from_str = positive))]
+    simulations: u32,
+    /// How to estimate win probabilities: by simulating, or exactly but ignoring stolen bases and
+    /// weather
+    #[structopt(long, default_value = "monte-carlo", possible_values = &["monte-carlo", "markov"])]
+    engine: Engine,
+}
+
+impl SimOpts {
+    pub fn away_odds(&self, game: &Playable, params: &ModelParams) -> Result<f64> {
+        match self.engine {
+            Engine::MonteCarlo => Ok(monte_carlo_with(
+                game,
+                &DefaultModel,
+                params,
+                self.simulations,
+            )),
+            Engine::Markov => exact_odds(game, params),
+        }
+    }
+
+    /// Like [`SimOpts::away_odds`], but taking the simulated odds from `scores`, which came from
+    /// simulating `game` as many times, instead of simulating it all over again.
+    pub fn away_odds_from(
+        &self,
+        game: &Playable,
+        params: &ModelParams,
+        scores: &ScoreDistribution,
+    ) -> Result<f64> {
+        match self.engine {
+            Engine::MonteCarlo => Ok(scores.away_odds()),
+            Engine::Markov => exact_odds(game, params),
+        }
+    }
+}
+
+/// Solves `game` with the Markov engine, warning the first time that's only approximate because
+/// of steals or weather it ignores.
+fn exact_odds(game: &Playable, params: &ModelParams) -> Result<f64> {
+    static INEXACT: Once = Once::new();
+    if !markov::is_exact(game, params) {
+        INEXACT.call_once(|| {
+            warn!(
+                "the Markov engine ignores stolen bases and weather, so its odds are approximate"
+            );
+        });
+    }
+    markov::win_probability(game, &DefaultModel, params)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Engine {
+    MonteCarlo,
+    Markov,
+}
+
+impl FromStr for Engine {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Engine> {
+        match s {
+            "monte-carlo" => Ok(Engine::MonteCarlo),
+            "markov" => Ok(Engine::Markov),
+            _ => bail!("unknown engine {:?}", s),
+        }
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+#[derive(Debug, Clone, Copy)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Format> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => bail!("unknown output format {:?}", s),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct OutputOpts {
+    /// Output format
+    #[structopt(long, default_value = "text", possible_values = &["text", "json"])]
+    format: Format,
+}
+
+impl OutputOpts {
+    /// Prints `value` as JSON, or calls `text` to print it for humans.
+    pub fn print<T, F>(&self, value: &T, text: F) -> Result<()>
+    where
+        T: Serialize,
+        F: FnOnce(&T),
+    {
+        match self.format {
+            Format::Text => text(value),
+            Format::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::cmd::{DatabaseOpts, GameOpts, ModelOpts};
+use anyhow::{anyhow, Result};
+use metasim::{AwayHome, DefaultModel, Narrator};
+use structopt::StructOpt;
+use uuid::Uuid;
+
+#[derive(Debug, StructOpt)]
+pub struct Narrate {
+    /// ID of the game to simulate
+    game: Uuid,
+    /// Seed for the simulation; the same seed always tells the same story
+    #[structopt(long, default_value = "0")]
+    seed: u64,
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    games: GameOpts,
+    #[structopt(flatten)]
+    model: ModelOpts,
+}
+
+impl Narrate {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let game = self.games.find(self.game)?;
+        let playable = game
+            .playable(&database)
+            .ok_or_else(|| anyhow!("unable to resolve rosters for game {}", game.id))?;
+
+        let mut narrator = Narrator::new(AwayHome {
+            away: database.team_name(game.away_team, game.timestamp()),
+            home: database.team_name(game.home_team, game.timestamp()),
+        });
+        playable.simulate_observed(&DefaultModel, &self.model.load()?, self.seed, &mut narrator);
+        for line in &narrator.lines {
+            println!("{line}");
+        }
+        Ok(())
+    }
+}
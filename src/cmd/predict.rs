@@ -0,0 +1,130 @@
+use crate::cmd::{load_games, DatabaseOpts, Filter, GameOpts, ModelOpts, OutputOpts, SimOpts};
+use anyhow::Result;
+use metasim::{score_distribution, DefaultModel, ModelParams, ScoreDistribution};
+use serde::Serialize;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use uuid::Uuid;
+
+#[derive(Debug, StructOpt)]
+pub struct Predict {
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    games: GameOpts,
+    /// JSON file of upcoming games, shaped like game-data but without scores, to predict instead
+    /// of game-data; rosters are taken from the latest data in the database
+    #[structopt(long, parse(from_os_str))]
+    schedule: Option<PathBuf>,
+    #[structopt(flatten)]
+    filter: Filter,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    #[structopt(flatten)]
+    sim: SimOpts,
+    #[structopt(flatten)]
+    output: OutputOpts,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    predictions: Vec<Prediction>,
+    params: ModelParams,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Prediction {
+    id: Uuid,
+    season: u16,
+    day: u8,
+    away_team: String,
+    home_team: String,
+    official_odds: Option<f64>,
+    away_odds: Option<f64>,
+    scores: Option<ScoreDistribution>,
+}
+
+impl Predict {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let params = self.model.load()?;
+
+        let games = match &self.schedule {
+            Some(schedule) => load_games(schedule, &self.filter)?,
+            None => self.games.load(&self.filter)?,
+        };
+        let predictions = games
+            .into_iter()
+            .map(|game| {
+                let time = if self.schedule.is_some() {
+                    u64::MAX
+                } else {
+                    game.timestamp()
+                };
+                let playable = game.playable_at(&database, time);
+                let scores = playable.as_ref().map(|playable| {
+                    score_distribution(playable, &DefaultModel, &params, self.sim.simulations)
+                });
+                Ok(Prediction {
+                    id: game.id,
+                    season: game.season + 1,
+                    day: game.day + 1,
+                    away_team: database.team_name(game.away_team, time),
+                    home_team: database.team_name(game.home_team, time),
+                    official_odds: game.away_odds.filter(|odds| *odds > 0.0),
+                    away_odds: playable
+                        .as_ref()
+                        .zip(scores.as_ref())
+                        .map(|(playable, scores)| {
+                            self.sim.away_odds_from(playable, &params, scores)
+                        })
+                        .transpose()?,
+                    scores,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let report = Report {
+            predictions,
+            params,
+        };
+        self.output.print(&report, |report| {
+            for p in &report.predictions {
+                let official = p
+                    .official_odds
+                    .map_or_else(|| "unknown".to_string(), |odds| format!("{odds:.3}"));
+                let (ours, scores) = match (p.away_odds, &p.scores) {
+                    (Some(odds), Some(scores)) => {
+                        let likely = scores
+                            .final_scores
+                            .iter()
+                            .take(3)
+                            .map(|score| {
+                                format!(
+                                    "{}-{} ({:.1}%)",
+                                    score.away,
+                                    score.home,
+                                    score.probability * 100.0
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        (
+                            format!("{odds:.3}"),
+                            format!(
+                                "; expected score {:.2}-{:.2}, most likely {}",
+                                scores.expected.away, scores.expected.home, likely
+                            ),
+                        )
+                    }
+                    _ => ("unplayable".to_string(), String::new()),
+                };
+                println!(
+                    "s{}d{} {} @ {}: ours {}, official {}{}",
+                    p.season, p.day, p.away_team, p.home_team, ours, official, scores
+                );
+            }
+        })
+    }
+}
@@ -0,0 +1,279 @@
+use crate::cmd::{load_feeds, positive, DatabaseOpts, Filter, ModelOpts, OutputOpts};
+use anyhow::{bail, Result};
+use metasim::{project, BattingLine, DefaultModel, GameResult};
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use uuid::Uuid;
+
+/// Each game in the archived feeds is simulated from its rosters on the day, so both sides cover
+/// the same games and players.
+#[derive(Debug, StructOpt)]
+pub struct Rates {
+    /// JSON file or directory of game updates, in order; see `chart` for the formats read
+    #[structopt(parse(from_os_str))]
+    updates: PathBuf,
+    #[structopt(flatten)]
+    filter: Filter,
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    /// Number of simulations to run per game
+    #[structopt(short = "n", long, default_value = "100", parse(try_from_str = positive))]
+    simulations: u32,
+    /// Only list players with at least this many actual plate appearances
+    #[structopt(long, default_value = "20")]
+    min_plate_appearances: u32,
+    #[structopt(flatten)]
+    output: OutputOpts,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    games: usize,
+    skipped_games: usize,
+    simulations: u32,
+    league: Comparison<LeagueRates>,
+    players: Vec<PlayerRates>,
+}
+
+#[derive(Debug, Serialize)]
+struct Comparison<T> {
+    simulated: T,
+    actual: T,
+}
+
+/// Every rate is per plate appearance unless it says otherwise.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BattingRates {
+    strikeouts: f64,
+    walks: f64,
+    singles: f64,
+    doubles: f64,
+    triples: f64,
+    home_runs: f64,
+    fly_outs: f64,
+    ground_outs: f64,
+    double_plays: f64,
+    /// Batting average on balls in play, counting home runs as out of play.
+    babip: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LeagueRates {
+    #[serde(flatten)]
+    batting: BattingRates,
+    plate_appearances_per_game: f64,
+    double_plays_per_game: f64,
+    stolen_bases_per_game: f64,
+    caught_stealing_per_game: f64,
+    runs_per_game: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayerRates {
+    id: Uuid,
+    name: String,
+    plate_appearances: u32,
+    simulated: BattingRates,
+    actual: BattingRates,
+}
+
+/// Batting lines and runs summed over some number of games, simulated or actual.
+#[derive(Debug, Default)]
+struct Totals {
+    games: u32,
+    runs: u32,
+    league: BattingLine,
+    players: FxHashMap<Uuid, BattingLine>,
+}
+
+impl Totals {
+    fn add(&mut self, games: u32, runs: u32, lines: &[BattingLine]) {
+        self.games += games;
+        self.runs += runs;
+        for line in lines {
+            self.league.add(line);
+            self.players
+                .entry(line.id)
+                .or_insert_with(|| BattingLine {
+                    id: line.id,
+                    name: line.name.clone(),
+                    ..BattingLine::default()
+                })
+                .add(line);
+        }
+    }
+
+    fn league(&self) -> LeagueRates {
+        let games = f64::from(self.games.max(1));
+        LeagueRates {
+            batting: BattingRates::new(&self.league),
+            plate_appearances_per_game: f64::from(self.league.plate_appearances) / games,
+            double_plays_per_game: f64::from(self.league.double_plays) / games,
+            stolen_bases_per_game: f64::from(self.league.stolen_bases) / games,
+            caught_stealing_per_game: f64::from(self.league.caught_stealing) / games,
+            runs_per_game: f64::from(self.runs) / games,
+        }
+    }
+}
+
+impl BattingRates {
+    fn new(line: &BattingLine) -> BattingRates {
+        let per = |n: u32| f64::from(n) / f64::from(line.plate_appearances.max(1));
+        let singles = line.hits - line.doubles - line.triples - line.home_runs;
+        let in_play = line.plate_appearances - line.walks - line.strikeouts - line.home_runs;
+        BattingRates {
+            strikeouts: per(line.strikeouts),
+            walks: per(line.walks),
+            singles: per(singles),
+            doubles: per(line.doubles),
+            triples: per(line.triples),
+            home_runs: per(line.home_runs),
+            fly_outs: per(line.fly_outs),
+            ground_outs: per(line.ground_outs),
+            double_plays: per(line.double_plays),
+            babip: f64::from(line.hits - line.home_runs) / f64::from(in_play.max(1)),
+        }
+    }
+}
+
+impl Rates {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let params = self.model.load()?;
+        let (feeds, skipped_games) = load_feeds(&self.updates, &self.filter, &database)?;
+        if feeds.is_empty() {
+            bail!("no playable games in {}", self.updates.display());
+        }
+
+        let mut actual = Totals::default();
+        for (game, feed) in &feeds {
+            let result = GameResult::from_feed(game, feed);
+            let runs = u32::from(result.score.score.away) + u32::from(result.score.score.home);
+            let lines = [result.batting.away, result.batting.home].concat();
+            actual.add(1, runs, &lines);
+        }
+
+        let projections = feeds
+            .par_iter()
+            .map(|(game, _)| project(game, &DefaultModel, &params, self.simulations))
+            .collect::<Vec<_>>();
+        let mut simulated = Totals::default();
+        for projection in projections {
+            let lines = [projection.batting.away, projection.batting.home].concat();
+            let runs = projection.runs.away + projection.runs.home;
+            simulated.add(projection.simulations, runs, &lines);
+        }
+
+        let mut players = actual
+            .players
+            .values()
+            .filter(|line| line.plate_appearances >= self.min_plate_appearances)
+            .map(|line| PlayerRates {
+                id: line.id,
+                name: line.name.clone(),
+                plate_appearances: line.plate_appearances,
+                simulated: simulated
+                    .players
+                    .get(&line.id)
+                    .map(BattingRates::new)
+                    .unwrap_or_default(),
+                actual: BattingRates::new(line),
+            })
+            .collect::<Vec<_>>();
+        players.sort_by(|a, b| {
+            b.plate_appearances
+                .cmp(&a.plate_appearances)
+                .then(a.id.cmp(&b.id))
+        });
+
+        let report = Report {
+            games: feeds.len(),
+            skipped_games,
+            simulations: self.simulations,
+            league: Comparison {
+                simulated: simulated.league(),
+                actual: actual.league(),
+            },
+            players,
+        };
+        self.output.print(&report, print_text)
+    }
+}
+
+fn print_text(report: &Report) {
+    println!(
+        "{} games ({} skipped), simulated {} times each",
+        report.games, report.skipped_games, report.simulations
+    );
+    println!();
+    println!(
+        "{:<16} {:>9} {:>9} {:>9}",
+        "", "simulated", "actual", "diff"
+    );
+    let (s, a) = (&report.league.simulated, &report.league.actual);
+    for (name, simulated, actual) in [
+        ("K%", s.batting.strikeouts, a.batting.strikeouts),
+        ("BB%", s.batting.walks, a.batting.walks),
+        ("1B%", s.batting.singles, a.batting.singles),
+        ("2B%", s.batting.doubles, a.batting.doubles),
+        ("3B%", s.batting.triples, a.batting.triples),
+        ("HR%", s.batting.home_runs, a.batting.home_runs),
+        ("FO%", s.batting.fly_outs, a.batting.fly_outs),
+        ("GO%", s.batting.ground_outs, a.batting.ground_outs),
+        ("DP%", s.batting.double_plays, a.batting.double_plays),
+        ("BABIP", s.batting.babip, a.batting.babip),
+        (
+            "PA/game",
+            s.plate_appearances_per_game,
+            a.plate_appearances_per_game,
+        ),
+        ("DP/game", s.double_plays_per_game, a.double_plays_per_game),
+        ("SB/game", s.stolen_bases_per_game, a.stolen_bases_per_game),
+        (
+            "CS/game",
+            s.caught_stealing_per_game,
+            a.caught_stealing_per_game,
+        ),
+        ("runs/game", s.runs_per_game, a.runs_per_game),
+    ] {
+        println!(
+            "{:<16} {:>9.3} {:>9.3} {:>+9.3}",
+            name,
+            simulated,
+            actual,
+            simulated - actual
+        );
+    }
+
+    if !report.players.is_empty() {
+        println!();
+        println!(
+            "{:<24} {:>4}  {:>11}  {:>11}  {:>11}  {:>11}",
+            "simulated / actual", "PA", "K%", "BB%", "HR%", "BABIP"
+        );
+        for p in &report.players {
+            println!(
+                "{:<24} {:>4}  {:.3}/{:.3}  {:.3}/{:.3}  {:.3}/{:.3}  {:.3}/{:.3}",
+                p.name,
+                p.plate_appearances,
+                p.simulated.strikeouts,
+                p.actual.strikeouts,
+                p.simulated.walks,
+                p.actual.walks,
+                p.simulated.home_runs,
+                p.actual.home_runs,
+                p.simulated.babip,
+                p.actual.babip
+            );
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::cmd::{group_updates, load_updates, DatabaseOpts, Filter, ModelOpts, OutputOpts};
+use anyhow::{anyhow, Result};
+use metasim::{replay, Divergence, Mismatch};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use uuid::Uuid;
+
+/// Runner advancement is random in the simulation, so any advance it could have rolled counts as
+/// a match. Everything after a game's first divergence is skipped, since the states no longer
+/// line up.
+#[derive(Debug, StructOpt)]
+pub struct Replay {
+    /// JSON file or directory of game updates, in order; see `chart` for the formats read
+    #[structopt(parse(from_os_str))]
+    updates: PathBuf,
+    #[structopt(flatten)]
+    filter: Filter,
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    #[structopt(flatten)]
+    output: OutputOpts,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GameReplay {
+    id: Uuid,
+    /// Why the game couldn't be replayed at all, e.g. its rosters couldn't be resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    events: usize,
+    unparsed: usize,
+    divergence: Option<Divergence>,
+}
+
+impl Replay {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let params = self.model.load()?;
+
+        let games = group_updates(load_updates(&self.updates, &self.filter)?)
+            .into_iter()
+            .map(|updates| {
+                let id = updates[0].id;
+                let result = updates[0]
+                    .playable(&database)
+                    .ok_or_else(|| anyhow!("unable to resolve rosters"))
+                    .and_then(|game| replay(&game, &updates, &params));
+                match result {
+                    Ok(replay) => GameReplay {
+                        id,
+                        error: None,
+                        events: replay.events,
+                        unparsed: replay.unparsed,
+                        divergence: replay.divergence,
+                    },
+                    Err(err) => GameReplay {
+                        id,
+                        error: Some(err.to_string()),
+                        events: 0,
+                        unparsed: 0,
+                        divergence: None,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.output.print(&games, |games| {
+            let mut mismatches: BTreeMap<Mismatch, usize> = BTreeMap::new();
+            for game in games {
+                match (&game.error, &game.divergence) {
+                    (Some(error), _) => println!("{}: skipped, {}", game.id, error),
+                    (None, None) => println!("{}: matched all {} events", game.id, game.events),
+                    (None, Some(divergence)) => {
+                        *mismatches.entry(divergence.mismatch).or_default() += 1;
+                        let (expected, actual) = (&divergence.expected, &divergence.actual);
+                        println!(
+                            "{}: first differs on {} at update {}, after {} events: {}",
+                            game.id,
+                            divergence.mismatch,
+                            divergence.update,
+                            game.events,
+                            divergence.text
+                        );
+                        println!(
+                            "    expected {} out, {}-{}, runners {}, {}-{}",
+                            expected.outs,
+                            expected.balls,
+                            expected.strikes,
+                            runners(&expected.bases),
+                            expected.score.away,
+                            expected.score.home
+                        );
+                        println!(
+                            "    archived {} out, {}-{}, runners {}, {}-{}",
+                            actual.outs,
+                            actual.balls,
+                            actual.strikes,
+                            runners(&actual.bases),
+                            actual.score.away,
+                            actual.score.home
+                        );
+                    }
+                }
+            }
+            println!();
+            let matched = games
+                .iter()
+                .filter(|game| game.error.is_none() && game.divergence.is_none())
+                .count();
+            println!("{} of {} games matched throughout", matched, games.len());
+            for (mismatch, count) in &mismatches {
+                println!("{count:>7}  first differed on {mismatch}");
+            }
+        })
+    }
+}
+
+/// Which bases are occupied, like "1-3" for first and third, or "none".
+fn runners(bases: &[Option<Uuid>]) -> String {
+    let occupied = (1..)
+        .zip(bases)
+        .filter(|(_, runner)| runner.is_some())
+        .map(|(base, _)| base.to_string())
+        .collect::<Vec<_>>();
+    if occupied.is_empty() {
+        "none".to_string()
+    } else {
+        occupied.join("-")
+    }
+}
@@ -0,0 +1,159 @@
+use crate::cmd::{positive, DatabaseOpts, GameOpts, ModelOpts, OutputOpts};
+use anyhow::{anyhow, bail, Result};
+use metasim::{
+    markov, project, score_distribution, score_distribution_from, DefaultModel, ModelParams,
+    Projection,
+};
+use serde::Serialize;
+use structopt::StructOpt;
+use uuid::Uuid;
+
+#[derive(Debug, StructOpt)]
+pub struct Simulate {
+    /// ID of the game to simulate
+    game: Uuid,
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    games: GameOpts,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    /// Number of simulations to run
+    #[structopt(short = "n", long, default_value = "1000", parse(try_from_str = positive))]
+    simulations: u32,
+    /// Also project each player's stat line per game
+    #[structopt(long)]
+    box_score: bool,
+    /// Pick the game up from where its record left off, e.g. a game update archived mid-game,
+    /// instead of from the first pitch
+    #[structopt(long)]
+    resume: bool,
+    #[structopt(flatten)]
+    output: OutputOpts,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    id: Uuid,
+    simulations: u32,
+    away_odds: f64,
+    /// Not computed when resuming a game in progress.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exact_odds: Option<f64>,
+    official_odds: Option<f64>,
+    away_runs: f64,
+    home_runs: f64,
+    actual: (u16, u16),
+    #[serde(skip_serializing_if = "Option::is_none")]
+    projection: Option<Projection>,
+    params: ModelParams,
+}
+
+impl Simulate {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let params = self.model.load()?;
+        let game = self.games.find(self.game)?;
+        let playable = game
+            .playable(&database)
+            .ok_or_else(|| anyhow!("unable to resolve rosters for game {}", game.id))?;
+
+        if self.resume && self.box_score {
+            bail!("--box-score projects whole games, so it can't be combined with --resume");
+        }
+
+        let distribution = if self.resume {
+            score_distribution_from(
+                &playable,
+                &game.snapshot,
+                &DefaultModel,
+                &params,
+                self.simulations,
+            )?
+        } else {
+            score_distribution(&playable, &DefaultModel, &params, self.simulations)
+        };
+        let summary = Summary {
+            id: game.id,
+            simulations: self.simulations,
+            away_odds: distribution.away_odds(),
+            exact_odds: if self.resume {
+                None
+            } else {
+                Some(markov::win_probability(&playable, &DefaultModel, &params)?)
+            },
+            official_odds: game.away_odds,
+            away_runs: distribution.expected.away,
+            home_runs: distribution.expected.home,
+            actual: (game.snapshot.score().away, game.snapshot.score().home),
+            projection: if self.box_score {
+                Some(project(&playable, &DefaultModel, &params, self.simulations))
+            } else {
+                None
+            },
+            params,
+        };
+
+        self.output.print(&summary, |s| {
+            println!("{playable:?}");
+            let official = s
+                .official_odds
+                .map_or_else(|| "unknown".to_string(), |odds| format!("{odds:.3}"));
+            match s.exact_odds {
+                Some(exact_odds) => println!(
+                    "away win probability: {:.3} (exact {:.3}, official {})",
+                    s.away_odds, exact_odds, official
+                ),
+                None => println!(
+                    "away win probability from here: {:.3} (official pregame {})",
+                    s.away_odds, official
+                ),
+            }
+            println!(
+                "average score: {:.2}-{:.2} (actual {}-{})",
+                s.away_runs, s.home_runs, s.actual.0, s.actual.1
+            );
+            if let Some(projection) = &s.projection {
+                print_projection(projection);
+            }
+        })
+    }
+}
+
+/// Prints per-game averages from `projection`.
+fn print_projection(projection: &Projection) {
+    let n = f64::from(projection.simulations);
+    for (team, batting, pitching) in [
+        ("away", &projection.batting.away, &projection.pitching.away),
+        ("home", &projection.batting.home, &projection.pitching.home),
+    ] {
+        println!();
+        println!(
+            "{:<24} {:>5} {:>5} {:>5} {:>5} {:>5} {:>5} {:>5} {:>5} {:>5}",
+            team, "PA", "H", "2B", "3B", "HR", "BB", "K", "RBI", "R"
+        );
+        for line in batting {
+            println!(
+                "{:<24} {:>5.2} {:>5.2} {:>5.2} {:>5.2} {:>5.2} {:>5.2} {:>5.2} {:>5.2} {:>5.2}",
+                line.name,
+                f64::from(line.plate_appearances) / n,
+                f64::from(line.hits) / n,
+                f64::from(line.doubles) / n,
+                f64::from(line.triples) / n,
+                f64::from(line.home_runs) / n,
+                f64::from(line.walks) / n,
+                f64::from(line.strikeouts) / n,
+                f64::from(line.runs_batted_in) / n,
+                f64::from(line.runs) / n,
+            );
+        }
+        println!(
+            "{:<24} {:>5.1} pitches, {:.2} K, {:.2} ER",
+            pitching.name,
+            f64::from(pitching.pitches) / n,
+            f64::from(pitching.strikeouts) / n,
+            f64::from(pitching.earned_runs) / n,
+        );
+    }
+}
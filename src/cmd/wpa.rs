@@ -0,0 +1,279 @@
+use crate::cmd::{group_updates, load_updates, DatabaseOpts, Filter, ModelOpts, OutputOpts};
+use anyhow::Result;
+use metasim::markov::WinProbability;
+use metasim::{Database, DefaultModel, Game, ModelParams, Playable, Situation};
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use uuid::Uuid;
+
+/// Win probabilities here are exact, from the `markov` engine.
+#[derive(Debug, StructOpt)]
+pub struct Wpa {
+    /// JSON file or directory of game updates, in order; see `chart` for the formats read
+    #[structopt(parse(from_os_str))]
+    updates: PathBuf,
+    #[structopt(flatten)]
+    filter: Filter,
+    #[structopt(flatten)]
+    database: DatabaseOpts,
+    #[structopt(flatten)]
+    model: ModelOpts,
+    /// Only list players with at least this many plate appearances or batters faced
+    #[structopt(long, default_value = "0")]
+    min_plate_appearances: u32,
+    #[structopt(flatten)]
+    output: OutputOpts,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    games: usize,
+    updates: usize,
+    /// Games whose rosters couldn't be resolved.
+    skipped_games: usize,
+    /// Updates that couldn't be turned into a situation, e.g. a runner isn't in the lineup.
+    skipped_updates: usize,
+    players: Vec<PlayerValue>,
+}
+
+/// A player's win probability added, from the point of view of their own team.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayerValue {
+    id: Uuid,
+    name: String,
+    batting: f64,
+    plate_appearances: u32,
+    /// Mean leverage index of the player's plate appearances, where 1 is average.
+    batting_leverage: f64,
+    pitching: f64,
+    batters_faced: u32,
+    pitching_leverage: f64,
+}
+
+impl PlayerValue {
+    fn total(&self) -> f64 {
+        self.batting + self.pitching
+    }
+
+    fn add(&mut self, other: &PlayerValue) {
+        self.batting += other.batting;
+        self.plate_appearances += other.plate_appearances;
+        self.batting_leverage += other.batting_leverage;
+        self.pitching += other.pitching;
+        self.batters_faced += other.batters_faced;
+        self.pitching_leverage += other.pitching_leverage;
+    }
+}
+
+/// Everything credited in one game. Leverage is summed, not yet averaged or scaled.
+#[derive(Debug, Default)]
+struct GameValues {
+    updates: usize,
+    skipped_updates: usize,
+    players: FxHashMap<Uuid, PlayerValue>,
+}
+
+impl GameValues {
+    fn player(&mut self, database: &Database, time: u64, id: Uuid) -> &mut PlayerValue {
+        self.players.entry(id).or_insert_with(|| PlayerValue {
+            id,
+            name: database
+                .players
+                .get(&id)
+                .and_then(|history| history.get(time))
+                .map_or_else(|| id.to_string(), |player| player.name.clone()),
+            ..PlayerValue::default()
+        })
+    }
+}
+
+impl Wpa {
+    pub fn run(self) -> Result<()> {
+        let database = self.database.load()?;
+        let params = self.model.load()?;
+
+        let games = group_updates(load_updates(&self.updates, &self.filter)?);
+
+        let values = games
+            .par_iter()
+            .map(|updates| credit(&database, &params, updates))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut report = Report {
+            games: games.len(),
+            ..Report::default()
+        };
+        let mut players: FxHashMap<Uuid, PlayerValue> = FxHashMap::default();
+        for game in values {
+            let Some(game) = game else {
+                report.skipped_games += 1;
+                continue;
+            };
+            report.updates += game.updates;
+            report.skipped_updates += game.skipped_updates;
+            for (id, value) in game.players {
+                players.entry(id).or_insert_with(|| PlayerValue {
+                    id,
+                    name: value.name.clone(),
+                    ..PlayerValue::default()
+                });
+                if let Some(total) = players.get_mut(&id) {
+                    total.add(&value);
+                }
+            }
+        }
+
+        // scale leverage so the average plate appearance is 1
+        let (leverage, plate_appearances) = players.values().fold((0.0, 0), |(l, n), p| {
+            (l + p.batting_leverage, n + p.plate_appearances)
+        });
+        let average = leverage / f64::from(plate_appearances.max(1));
+        for player in players.values_mut() {
+            if average > 0.0 {
+                player.batting_leverage /= average * f64::from(player.plate_appearances.max(1));
+                player.pitching_leverage /= average * f64::from(player.batters_faced.max(1));
+            }
+        }
+
+        report.players = players
+            .into_values()
+            .filter(|player| {
+                player.plate_appearances.max(player.batters_faced) >= self.min_plate_appearances
+            })
+            .collect();
+        report
+            .players
+            .sort_by(|a, b| b.total().total_cmp(&a.total()).then(a.id.cmp(&b.id)));
+
+        self.output.print(&report, |report| {
+            println!(
+                "{} games, {} updates ({} games and {} updates skipped)",
+                report.games, report.updates, report.skipped_games, report.skipped_updates
+            );
+            println!();
+            println!(
+                "{:<24} {:>7} {:>5} {:>5}  {:>7} {:>5} {:>5}",
+                "", "bat WPA", "PA", "LI", "pit WPA", "BF", "LI"
+            );
+            for p in &report.players {
+                println!(
+                    "{:<24} {:>+7.3} {:>5} {:>5.2}  {:>+7.3} {:>5} {:>5.2}",
+                    p.name,
+                    p.batting,
+                    p.plate_appearances,
+                    p.batting_leverage,
+                    p.pitching,
+                    p.batters_faced,
+                    p.pitching_leverage
+                );
+            }
+        })
+    }
+}
+
+/// Credits each change in the away team's chance of winning between consecutive updates to the
+/// batter who was up and the pitcher facing them. Returns `None` if the rosters can't be resolved,
+/// and an error if the game can't be solved.
+fn credit(
+    database: &Database,
+    params: &ModelParams,
+    updates: &[Game],
+) -> Result<Option<GameValues>> {
+    let Some(first) = updates.first() else {
+        return Ok(None);
+    };
+    let time = first.timestamp();
+    let Some(playable) = first.playable(database) else {
+        return Ok(None);
+    };
+    let solution = WinProbability::new(&playable, &DefaultModel, params)?;
+
+    let mut values = GameValues {
+        updates: updates.len(),
+        ..GameValues::default()
+    };
+
+    let mut skipped = 0;
+    let mut previous: Option<(&Game, Situation, f64)> = None;
+    for update in updates {
+        let Ok(situation) = playable.situation(&update.snapshot) else {
+            skipped += 1;
+            previous = None;
+            continue;
+        };
+        let away_odds = solution.at(&situation);
+
+        if let Some((before, before_situation, before_odds)) = &previous {
+            let bottom = before_situation.bottom;
+            // between plate appearances, this is the batter coming up
+            let batter = batter(&playable, before, before_situation);
+            let added = if bottom {
+                before_odds - away_odds
+            } else {
+                away_odds - before_odds
+            };
+            values.player(database, time, batter).batting += added;
+            values
+                .player(database, time, pitcher(before, bottom))
+                .pitching -= added;
+        }
+
+        // a new plate appearance, credited with the leverage of its situation before any pitches
+        let batter = batter(&playable, update, &situation);
+        let continuing = previous.is_some_and(|(before, before_situation, _)| {
+            before_situation.bottom == situation.bottom
+                && self::batter(&playable, before, &before_situation) == batter
+        });
+        if !continuing {
+            let leverage = solution.leverage(&Situation {
+                balls: 0,
+                strikes: 0,
+                ..situation.clone()
+            });
+            let hitter = values.player(database, time, batter);
+            hitter.plate_appearances += 1;
+            hitter.batting_leverage += leverage;
+            let pitcher = values.player(database, time, pitcher(update, situation.bottom));
+            pitcher.batters_faced += 1;
+            pitcher.pitching_leverage += leverage;
+        }
+
+        previous = Some((update, situation, away_odds));
+    }
+
+    values.skipped_updates = skipped;
+    Ok(Some(values))
+}
+
+/// The batter up at `update`, or coming up next if no one is.
+fn batter(playable: &Playable, update: &Game, situation: &Situation) -> Uuid {
+    let (batter, lineup, position) = if situation.bottom {
+        let position = situation.batters.home;
+        (
+            update.snapshot.home_batter,
+            &playable.lineups.home,
+            position,
+        )
+    } else {
+        let position = situation.batters.away;
+        (
+            update.snapshot.away_batter,
+            &playable.lineups.away,
+            position,
+        )
+    };
+    batter.unwrap_or(lineup[usize::from(position)].id)
+}
+
+fn pitcher(update: &Game, bottom: bool) -> Uuid {
+    if bottom {
+        update.away_pitcher
+    } else {
+        update.home_pitcher
+    }
+}
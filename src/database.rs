@@ -12,14 +12,16 @@ use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
-const DATABASE_VERSION: u64 = 1;
+const DATABASE_VERSION: u64 = 2;
 
+/// Time series of every team and player seen in the SIBR archives, keyed by ID.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Database {
     pub teams: HashMap<Uuid, History<Team>>,
     pub players: HashMap<Uuid, History<Player>>,
 }
 
+/// A team's roster at a point in time.
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Team {
     #[serde(alias = "_id")]
@@ -29,6 +31,8 @@ pub struct Team {
     pub rotation: [Uuid; 5],
 }
 
+/// A player's attributes and modifications at a point in time. Fields the archives didn't have
+/// yet are `None` or empty.
 #[derive(Default, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Player {
@@ -60,6 +64,50 @@ pub struct Player {
     pub tragicness: f64,
     pub unthwackability: f64,
     pub watchfulness: f64,
+    /// Whether peanuts give the player an allergic reaction, rather than a yummy one.
+    #[serde(default, with = "nullable")]
+    pub peanut_allergy: bool,
+    pub soul: Option<u32>,
+    pub fate: Option<u32>,
+    pub total_fingers: Option<u32>,
+    /// Blood and coffee types, numbered as on the site.
+    pub blood: Option<u8>,
+    pub coffee: Option<u8>,
+    pub ritual: Option<String>,
+    /// The IDs of the player's items, empty if they have none.
+    pub bat: Option<String>,
+    pub armor: Option<String>,
+    /// Modifications like `FIREPROOF`, which last until removed, for the season, for the week and
+    /// for the game respectively.
+    #[serde(default, with = "nullable")]
+    pub perm_attr: Vec<String>,
+    #[serde(default, with = "nullable")]
+    pub seas_attr: Vec<String>,
+    #[serde(default, with = "nullable")]
+    pub week_attr: Vec<String>,
+    #[serde(default, with = "nullable")]
+    pub game_attr: Vec<String>,
+}
+
+/// For archive fields that are sometimes null, which are read as their default value. They're
+/// written as options too, so the database cache reads them back the same way.
+mod nullable {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T: Serialize, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_some(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de> + Default,
+        D: Deserializer<'de>,
+    {
+        Ok(Option::deserialize(deserializer)?.unwrap_or_default())
+    }
 }
 
 impl fmt::Debug for Player {
@@ -67,7 +115,7 @@ impl fmt::Debug for Player {
         f.debug_struct("Player")
             .field("id", &self.id)
             .field("name", &self.name)
-            .finish()
+            .finish_non_exhaustive()
     }
 }
 
@@ -101,6 +149,12 @@ struct CacheKey<'a> {
 }
 
 impl Database {
+    /// Builds the database from a directory of gzipped archive files, or loads it from the cache
+    /// if those files haven't changed since the last build.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or any archive file can't be read or parsed.
     pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self> {
         let dir = dir.as_ref();
         let entries = read_dir(dir)?;
@@ -131,7 +185,7 @@ impl Database {
                         }
                     }
                     _ => {}
-                };
+                }
             }
         }
 
@@ -146,6 +200,14 @@ impl Database {
         Ok(database)
     }
 
+    /// The team's nickname at `time`, or its ID if the team isn't known.
+    pub fn team_name(&self, id: Uuid, time: u64) -> String {
+        self.teams
+            .get(&id)
+            .and_then(|h| h.get(time))
+            .map_or_else(|| id.to_string(), |team| team.nickname.clone())
+    }
+
     fn load_from_cache(entries: &Entries) -> Result<Self> {
         let mut reader = GzDecoder::new(File::open(get_cache_path(entries)?)?);
         Ok(bincode::deserialize_from(&mut reader)?)
@@ -176,3 +238,93 @@ fn get_cache_path(entries: &Entries) -> Result<PathBuf> {
         .join(env!("CARGO_PKG_NAME"))
         .join(format!("db-{:x}.bincode.gz", hasher.finish())))
 }
+
+/// A player for tests with ID `id` and every attribute set to `x`.
+#[cfg(test)]
+pub(crate) fn test_player(id: u128, x: f64) -> Player {
+    Player {
+        id: Uuid::from_u128(id),
+        anticapitalism: x,
+        base_thirst: x,
+        buoyancy: x,
+        chasiness: x,
+        cinnamon: x,
+        coldness: x,
+        continuation: x,
+        divinity: x,
+        ground_friction: x,
+        indulgence: x,
+        laserlikeness: x,
+        martyrdom: x,
+        moxie: x,
+        musclitude: x,
+        omniscience: x,
+        overpowerment: x,
+        patheticism: x,
+        pressurization: x,
+        ruthlessness: x,
+        shakespearianism: x,
+        tenaciousness: x,
+        thwackability: x,
+        tragicness: x,
+        unthwackability: x,
+        watchfulness: x,
+        ..Player::default()
+    }
+}
+
+/// A lineup of [`test_player`]s with IDs counting up from `first`, and attributes starting at `x`
+/// for the leadoff hitter and going up by 0.05 a spot. Patheticism, which makes a batter worse, is
+/// one minus that instead, so a higher `x` is a better lineup.
+#[cfg(test)]
+pub(crate) fn test_lineup(first: u128, x: f64) -> [Player; 9] {
+    let mut lineup: [Player; 9] = Default::default();
+    for (i, player) in (0_u8..).zip(&mut lineup) {
+        *player = test_player(first + u128::from(i), x + 0.05 * f64::from(i));
+        player.patheticism = 1.0 - player.patheticism;
+    }
+    lineup
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Player;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_player() {
+        // every attribute is required, so start from a player with them all at 0
+        let player = |fields: Value| {
+            let mut value = serde_json::to_value(Player::default()).unwrap();
+            if let (Value::Object(value), Value::Object(fields)) = (&mut value, fields) {
+                value.extend(fields);
+            }
+            serde_json::from_value::<Player>(value).unwrap()
+        };
+
+        let jessica = player(json!({
+            "peanutAllergy": true,
+            "soul": 7,
+            "totalFingers": 11,
+            "blood": null,
+            "ritual": "Mantras",
+            "bat": "",
+            "permAttr": ["FIREPROOF"],
+            "seasAttr": null,
+        }));
+        assert!(jessica.peanut_allergy);
+        assert_eq!(jessica.soul, Some(7));
+        assert_eq!(jessica.fate, None);
+        assert_eq!(jessica.total_fingers, Some(11));
+        assert_eq!(jessica.blood, None);
+        assert_eq!(jessica.ritual.as_deref(), Some("Mantras"));
+        assert_eq!(jessica.perm_attr, ["FIREPROOF"]);
+        assert!(jessica.seas_attr.is_empty());
+
+        // the database cache isn't self-describing, so it has to read back what it writes
+        let cached: Player = bincode::deserialize(&bincode::serialize(&jessica).unwrap()).unwrap();
+        assert!(cached == jessica);
+
+        assert!(!player(json!({"peanutAllergy": null})).peanut_allergy);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::database::Player;
+use crate::game::Score;
+use crate::pitch::Pitch;
+use crate::stats::Rating;
+
+/// Something that happened in a simulated game. See [`Playable::simulate_observed`].
+///
+/// [`Playable::simulate_observed`]: crate::Playable::simulate_observed
+#[derive(Debug, Clone, Copy)]
+pub enum GameEvent<'a> {
+    /// A half-inning is starting.
+    HalfInningStart,
+    /// A pitch was thrown; `balls` and `strikes` are the count after it.
+    Pitch {
+        pitcher: &'a Player,
+        batter: &'a Player,
+        pitch: Pitch,
+        balls: u8,
+        strikes: u8,
+    },
+    Walk {
+        batter: &'a Player,
+    },
+    Strikeout {
+        batter: &'a Player,
+        pitcher: &'a Player,
+    },
+    /// The batter is out on a ball in play: a fly out, on which the runner on third may tag up, or
+    /// a ground out, on which runners may move up.
+    Out {
+        batter: &'a Player,
+        fly: bool,
+    },
+    /// The batter and the runner on third, if there is one, are out.
+    DoublePlay {
+        batter: &'a Player,
+        runner: Option<&'a Player>,
+    },
+    /// The runner on third, if there is one, is out and the batter takes first.
+    FieldersChoice {
+        batter: &'a Player,
+        runner: Option<&'a Player>,
+    },
+    /// `bases` is 1 for a single through 4 for a home run.
+    Hit {
+        batter: &'a Player,
+        bases: u8,
+    },
+    /// A runner moved up from one base to another, numbered from 0 for first; 3 is home.
+    Advance {
+        runner: &'a Player,
+        from: usize,
+        to: usize,
+    },
+    /// `runner` scored, driven in by `batter`; `None` when they stole home.
+    Run {
+        runner: &'a Player,
+        batter: Option<&'a Player>,
+    },
+    /// Between pitches, `runner` stole `base`, numbered from 0 for first, or home if `None`.
+    StolenBase {
+        runner: &'a Player,
+        base: Option<usize>,
+    },
+    /// Between pitches, `runner` was thrown out trying to steal `base`, numbered the same way.
+    CaughtStealing {
+        runner: &'a Player,
+        base: Option<usize>,
+    },
+    /// A solar eclipse incinerated `victim`, and `replacement` took their place for the rest of
+    /// the game.
+    Incineration {
+        victim: &'a Player,
+        replacement: &'a Player,
+    },
+    /// `player` swallowed a peanut, which lowered their attributes if they're `allergic` and
+    /// raised them if not.
+    PeanutReaction {
+        player: &'a Player,
+        allergic: bool,
+    },
+    /// Feedback made `batter` and `pitcher` swap teams, and places in the game.
+    Feedback {
+        batter: &'a Player,
+        pitcher: &'a Player,
+    },
+    /// Reverb shuffled a team's lineup.
+    Reverb {
+        home: bool,
+    },
+    /// The blooddrain moved some of `victim`'s `rating` over to `drainer`.
+    Blooddrain {
+        drainer: &'a Player,
+        victim: &'a Player,
+        rating: Rating,
+    },
+    /// The Sun 2 took 10 runs off the batting team's score, once they got to 10.
+    Sun2,
+    GameOver,
+}
+
+/// Receives every [`GameEvent`] of a simulated game as it happens, along with the score (and
+/// inning) at that moment. Closures taking the same arguments are observers too.
+pub trait GameObserver {
+    fn observe(&mut self, event: &GameEvent<'_>, score: &Score);
+}
+
+impl<F: FnMut(&GameEvent<'_>, &Score)> GameObserver for F {
+    fn observe(&mut self, event: &GameEvent<'_>, score: &Score) {
+        self(event, score);
+    }
+}
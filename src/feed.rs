@@ -0,0 +1,450 @@
+use crate::game::Game;
+use crate::pitch::Pitch;
+use crate::util::AwayHome;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// What a ticker line says happened, as written on the site in the early seasons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Play {
+    /// "Play ball!"
+    PlayBall,
+    /// "Top of 1, Tigers batting."
+    HalfInningStart,
+    /// "Jessica Telephone batting for the Firefighters."
+    BatterUp,
+    /// "Ball. 1-0"
+    Ball,
+    /// "Strike, looking. 0-1" or "Strike, swinging. 0-1"
+    Strike { looking: bool },
+    /// "Foul Ball. 0-2"
+    Foul,
+    /// "Jessica Telephone draws a walk."
+    Walk,
+    /// "Jessica Telephone strikes out looking." or "... swinging."
+    Strikeout { looking: bool },
+    /// "Jessica Telephone hit a ground out to Wyatt Quitter."
+    GroundOut,
+    /// "Jessica Telephone hit a flyout to Wyatt Quitter."
+    Flyout,
+    /// "Jessica Telephone hit into a double play!"
+    DoublePlay,
+    /// "Jessica Telephone reaches on fielder's choice. Wyatt Quitter out at second base."
+    FieldersChoice,
+    /// "Jessica Telephone hits a Single!", through "Triple!"
+    Hit { bases: u8 },
+    /// "Jessica Telephone hits a solo home run!", "... a 2-run home run!" or "... a grand slam!";
+    /// `runs` includes the batter.
+    HomeRun { runs: u8 },
+    /// "Jessica Telephone steals second base!"; `base` is the one stolen, numbered from 0 for
+    /// first, or `None` for home.
+    StolenBase { base: Option<u8> },
+    /// "Jessica Telephone gets caught stealing third base."
+    CaughtStealing { base: Option<u8> },
+    /// "Game over."
+    GameOver,
+}
+
+/// The count, outs, runners and score around a [`PlayEvent`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayState {
+    /// Zero for the first inning.
+    pub inning: u8,
+    pub bottom: bool,
+    pub outs: u8,
+    pub balls: u8,
+    pub strikes: u8,
+    /// The runner on each base before home, if any. There are as many bases as the batting
+    /// team's [`Rules::base_count`].
+    ///
+    /// [`Rules::base_count`]: crate::Rules::base_count
+    pub bases: Vec<Option<Uuid>>,
+    pub score: AwayHome<u16>,
+}
+
+/// One parsed update: what happened, who was involved, and how it changed the game.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayEvent {
+    /// The update's index in the feed.
+    pub update: usize,
+    pub play: Play,
+    /// `None` for plays between plate appearances, like [`Play::HalfInningStart`].
+    pub batter: Option<Uuid>,
+    pub pitcher: Option<Uuid>,
+    /// The runner who tried to steal, for [`Play::StolenBase`] and [`Play::CaughtStealing`].
+    pub runner: Option<Uuid>,
+    pub before: PlayState,
+    pub after: PlayState,
+}
+
+/// An update whose ticker line [`Play::parse`] doesn't understand.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Unparsed {
+    pub update: usize,
+    pub text: String,
+}
+
+/// The play-by-play of one game, from [`parse_feed`].
+#[derive(Debug, Default, Serialize)]
+pub struct Feed {
+    pub events: Vec<PlayEvent>,
+    pub unparsed: Vec<Unparsed>,
+}
+
+/// The plays whose lines start with a player's name, which may have spaces in it.
+const PHRASES: [(&str, Play); 19] = [
+    (" draws a walk.", Play::Walk),
+    (" strikes out looking.", Play::Strikeout { looking: true }),
+    (" strikes out swinging.", Play::Strikeout { looking: false }),
+    (" hit a ground out to ", Play::GroundOut),
+    (" hit a flyout to ", Play::Flyout),
+    (" hit into a double play!", Play::DoublePlay),
+    (" reaches on fielder's choice.", Play::FieldersChoice),
+    (" hits a Single!", Play::Hit { bases: 1 }),
+    (" hits a Double!", Play::Hit { bases: 2 }),
+    (" hits a Triple!", Play::Hit { bases: 3 }),
+    (" hits a solo home run!", Play::HomeRun { runs: 1 }),
+    (" hits a 2-run home run!", Play::HomeRun { runs: 2 }),
+    (" hits a 3-run home run!", Play::HomeRun { runs: 3 }),
+    (" hits a grand slam!", Play::HomeRun { runs: 4 }),
+    (" steals second base!", Play::StolenBase { base: Some(1) }),
+    (" steals third base!", Play::StolenBase { base: Some(2) }),
+    (" steals fourth base!", Play::StolenBase { base: Some(3) }),
+    (" steals home!", Play::StolenBase { base: None }),
+    (" batting for the ", Play::BatterUp),
+];
+
+impl Play {
+    /// Reads the play described at the start of a ticker line. Anything after it, like "Wyatt
+    /// Quitter scores!", is ignored; the runs show up in the score.
+    pub fn parse(text: &str) -> Option<Play> {
+        let text = text.trim();
+        if text == "Play ball!" {
+            return Some(Play::PlayBall);
+        }
+        if text.starts_with("Game over.") {
+            return Some(Play::GameOver);
+        }
+        if (text.starts_with("Top of ") || text.starts_with("Bottom of "))
+            && text.ends_with(" batting.")
+        {
+            return Some(Play::HalfInningStart);
+        }
+        if text.starts_with("Ball.") {
+            return Some(Play::Ball);
+        }
+        if text.starts_with("Strike, looking.") {
+            return Some(Play::Strike { looking: true });
+        }
+        if text.starts_with("Strike, swinging.") {
+            return Some(Play::Strike { looking: false });
+        }
+        if text.starts_with("Foul Ball.") {
+            return Some(Play::Foul);
+        }
+
+        // everything else starts with a player's name
+        if let Some((_, play)) = PHRASES.iter().find(|(phrase, _)| text.contains(phrase)) {
+            return Some(*play);
+        }
+        let caught = text.find(" gets caught stealing ")?;
+        match &text[caught + " gets caught stealing ".len()..] {
+            s if s.starts_with("second base") => Some(Play::CaughtStealing { base: Some(1) }),
+            s if s.starts_with("third base") => Some(Play::CaughtStealing { base: Some(2) }),
+            s if s.starts_with("fourth base") => Some(Play::CaughtStealing { base: Some(3) }),
+            s if s.starts_with("home") => Some(Play::CaughtStealing { base: None }),
+            _ => None,
+        }
+    }
+
+    /// The outcome of the pitch this play describes, if it describes one. The pitch that ends a
+    /// plate appearance is a ball for a walk and a strike for a strikeout.
+    pub fn pitch(self) -> Option<Pitch> {
+        match self {
+            Play::Ball | Play::Walk => Some(Pitch::Ball),
+            Play::Strike { .. } | Play::Strikeout { .. } => Some(Pitch::Strike),
+            Play::Foul => Some(Pitch::Foul),
+            Play::GroundOut | Play::Flyout | Play::DoublePlay | Play::FieldersChoice => {
+                Some(Pitch::Out)
+            }
+            Play::Hit { bases: 1 } => Some(Pitch::Single),
+            Play::Hit { bases: 2 } => Some(Pitch::Double),
+            Play::Hit { .. } => Some(Pitch::Triple),
+            Play::HomeRun { .. } => Some(Pitch::Dinger),
+            Play::PlayBall
+            | Play::HalfInningStart
+            | Play::BatterUp
+            | Play::StolenBase { .. }
+            | Play::CaughtStealing { .. }
+            | Play::GameOver => None,
+        }
+    }
+
+    /// Whether this play puts a batter or runner out.
+    pub fn is_out(self) -> bool {
+        matches!(
+            self,
+            Play::Strikeout { .. }
+                | Play::GroundOut
+                | Play::Flyout
+                | Play::DoublePlay
+                | Play::FieldersChoice
+                | Play::CaughtStealing { .. }
+        )
+    }
+
+    /// Whether this play happens during a plate appearance, so it has a batter and pitcher.
+    fn has_batter(self) -> bool {
+        !matches!(
+            self,
+            Play::PlayBall | Play::HalfInningStart | Play::GameOver
+        )
+    }
+}
+
+/// The base a runner steals `base` from, on a field with `bases` bases before home.
+pub(crate) fn stolen_from(base: Option<u8>, bases: usize) -> usize {
+    base.map_or(bases, usize::from).saturating_sub(1)
+}
+
+impl From<&Game> for PlayState {
+    fn from(update: &Game) -> PlayState {
+        let snapshot = &update.snapshot;
+        let mut bases = vec![None; update.rules.base_count(!snapshot.top_of_inning)];
+        for (base, runner) in snapshot.bases_occupied.iter().zip(&snapshot.base_runners) {
+            if let Some(slot) = bases.get_mut(*base) {
+                *slot = Some(*runner);
+            }
+        }
+        PlayState {
+            inning: snapshot.inning,
+            bottom: !snapshot.top_of_inning,
+            outs: snapshot.half_inning_outs,
+            balls: snapshot.at_bat_balls,
+            strikes: snapshot.at_bat_strikes,
+            bases,
+            score: snapshot.score(),
+        }
+    }
+}
+
+/// Parses the ticker lines of one game's updates, in order, into events. Each event's batter is
+/// whoever was up before it, or the batter it brings up if no one was. Updates with no ticker
+/// line, and repeats of the previous update, are skipped; lines [`Play::parse`] doesn't
+/// understand are collected in [`Feed::unparsed`].
+pub fn parse_feed(updates: &[Game]) -> Feed {
+    let mut feed = Feed::default();
+    let mut previous: Option<(&Game, PlayState)> = None;
+    for (i, update) in updates.iter().enumerate() {
+        let after = PlayState::from(update);
+        let text = update.snapshot.last_update.trim();
+        let repeat = previous.as_ref().is_some_and(|(before, state)| {
+            *state == after && before.snapshot.last_update == update.snapshot.last_update
+        });
+        if text.is_empty() || repeat {
+            previous = Some((update, after));
+            continue;
+        }
+
+        let (before_update, before) = previous.take().unwrap_or((update, after.clone()));
+        match Play::parse(text) {
+            Some(play) => {
+                let (batter, pitcher) = if play.has_batter() {
+                    let bottom = if play == Play::BatterUp {
+                        after.bottom
+                    } else {
+                        before.bottom
+                    };
+                    let batter = batter(before_update, bottom).or_else(|| batter(update, bottom));
+                    (batter, Some(pitcher(update, bottom)))
+                } else {
+                    (None, None)
+                };
+                let runner = match play {
+                    Play::StolenBase { base } | Play::CaughtStealing { base } => {
+                        let from = stolen_from(base, before.bases.len());
+                        before.bases.get(from).copied().flatten()
+                    }
+                    _ => None,
+                };
+                feed.events.push(PlayEvent {
+                    update: i,
+                    play,
+                    batter,
+                    pitcher,
+                    runner,
+                    before,
+                    after: after.clone(),
+                });
+            }
+            None => feed.unparsed.push(Unparsed {
+                update: i,
+                text: text.to_string(),
+            }),
+        }
+        previous = Some((update, after));
+    }
+    feed
+}
+
+fn batter(update: &Game, bottom: bool) -> Option<Uuid> {
+    if bottom {
+        update.snapshot.home_batter
+    } else {
+        update.snapshot.away_batter
+    }
+}
+
+fn pitcher(update: &Game, bottom: bool) -> Uuid {
+    if bottom {
+        update.away_pitcher
+    } else {
+        update.home_pitcher
+    }
+}
+
+/// A game update for tests: the start of a game with nil IDs, then `fields` (in the archive's
+/// camelCase) and the ticker line `text`.
+#[cfg(test)]
+pub(crate) fn test_update(text: &str, fields: serde_json::Value) -> Game {
+    let mut update = serde_json::json!({
+        "id": Uuid::nil(),
+        "season": 0,
+        "day": 0,
+        "awayPitcher": Uuid::nil(),
+        "awayTeam": Uuid::nil(),
+        "homePitcher": Uuid::nil(),
+        "homeTeam": Uuid::nil(),
+        "lastUpdate": text,
+    });
+    if let serde_json::Value::Object(fields) = fields {
+        for (key, value) in fields {
+            update[key] = value;
+        }
+    }
+    serde_json::from_value(update).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_feed, Play};
+    use crate::game::Game;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_parse() {
+        for (text, play) in [
+            ("Play ball!", Some(Play::PlayBall)),
+            (
+                "Top of 1, Mexico City Wild Wings batting.",
+                Some(Play::HalfInningStart),
+            ),
+            ("Ball. 1-0", Some(Play::Ball)),
+            ("Strike, looking. 1-1", Some(Play::Strike { looking: true })),
+            ("Foul Ball. 1-2", Some(Play::Foul)),
+            (
+                "Jessica Telephone strikes out swinging.",
+                Some(Play::Strikeout { looking: false }),
+            ),
+            (
+                "York Silk hit a flyout to Wyatt Quitter.",
+                Some(Play::Flyout),
+            ),
+            (
+                "York Silk hits a 2-run home run!",
+                Some(Play::HomeRun { runs: 2 }),
+            ),
+            (
+                "Nagomi Mcdaniel reaches on fielder's choice. York Silk out at second base.",
+                Some(Play::FieldersChoice),
+            ),
+            (
+                "Nagomi Mcdaniel gets caught stealing home.",
+                Some(Play::CaughtStealing { base: None }),
+            ),
+            (
+                "Runner Name steals fourth base!",
+                Some(Play::StolenBase { base: Some(3) }),
+            ),
+            ("Rogue Umpire incinerated Jaylen Hotdogfingers!", None),
+        ] {
+            assert_eq!(Play::parse(text), play, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_parse_feed() {
+        let [pitcher, batter, runner] = [1, 2, 3].map(Uuid::from_u128);
+        let update = |text: &str, balls: u8, bases: &[Uuid], batter: Option<Uuid>| -> Game {
+            serde_json::from_value(json!({
+                "id": Uuid::nil(),
+                "season": 4,
+                "day": 0,
+                "awayPitcher": pitcher,
+                "awayTeam": Uuid::nil(),
+                "homePitcher": Uuid::nil(),
+                "homeTeam": Uuid::nil(),
+                "lastUpdate": text,
+                "topOfInning": false,
+                "atBatBalls": balls,
+                "basesOccupied": (0..bases.len()).collect::<Vec<_>>(),
+                "baseRunners": bases,
+                "homeBatter": batter,
+            }))
+            .unwrap()
+        };
+        let updates = [
+            update("", 0, &[runner], Some(batter)),
+            update("Ball. 1-0", 1, &[runner], Some(batter)),
+            update("Ball. 1-0", 1, &[runner], Some(batter)),
+            update("Runner Name steals second base!", 1, &[], Some(batter)),
+            update("The Shelled One's Pods are watching.", 1, &[], Some(batter)),
+            update(
+                "Batter Name hits a Triple! Runner Name scores!",
+                0,
+                &[],
+                None,
+            ),
+        ];
+        let feed = parse_feed(&updates);
+
+        let plays = feed.events.iter().map(|e| e.play).collect::<Vec<_>>();
+        assert_eq!(
+            plays,
+            [
+                Play::Ball,
+                Play::StolenBase { base: Some(1) },
+                Play::Hit { bases: 3 }
+            ]
+        );
+        assert_eq!(feed.events[1].runner, Some(runner));
+        assert_eq!(feed.events[2].batter, Some(batter));
+        assert_eq!(feed.events[2].pitcher, Some(pitcher));
+        assert_eq!(feed.events[2].before.balls, 1);
+        assert_eq!(feed.unparsed.len(), 1);
+        assert_eq!(feed.unparsed[0].update, 4);
+
+        // with a fifth base, the runner on fourth is kept and is the one stealing home
+        let fifth_base = |text: &str, occupied: &[usize]| -> Game {
+            let mut game = update(text, 0, &[], Some(batter));
+            game.rules.bases.home = 5;
+            game.snapshot.bases_occupied = occupied.to_vec();
+            game.snapshot.base_runners = vec![runner; occupied.len()];
+            game
+        };
+        let feed = parse_feed(&[
+            fifth_base("", &[3]),
+            fifth_base("Runner Name steals home!", &[]),
+        ]);
+        assert_eq!(
+            feed.events[0].before.bases,
+            [None, None, None, Some(runner)]
+        );
+        assert_eq!(feed.events[0].runner, Some(runner));
+    }
+}
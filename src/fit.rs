@@ -0,0 +1,152 @@
+//! Derivative-free minimization for fitting [`ModelParams`](crate::ModelParams) to results.
+
+use std::cell::Cell;
+
+/// The best point found by [`nelder_mead`].
+#[derive(Debug, Clone)]
+pub struct Minimum {
+    pub x: Vec<f64>,
+    pub value: f64,
+    pub evaluations: usize,
+}
+
+/// Minimizes `f` with the Nelder-Mead simplex method, starting from a simplex around `x0` whose
+/// other vertices are `step` away along each axis.
+///
+/// Stops after `max_evaluations` calls to `f`, or once every vertex of the simplex is within
+/// `tolerance` of the best. `f` may return infinity to reject a point.
+#[allow(clippy::cast_precision_loss)]
+pub fn nelder_mead<F>(
+    mut f: F,
+    x0: &[f64],
+    step: f64,
+    max_evaluations: usize,
+    tolerance: f64,
+) -> Minimum
+where
+    F: FnMut(&[f64]) -> f64,
+{
+    const REFLECT: f64 = 1.0;
+    const EXPAND: f64 = 2.0;
+    const CONTRACT: f64 = 0.5;
+    const SHRINK: f64 = 0.5;
+
+    let n = x0.len();
+    let evaluations = Cell::new(0);
+    let mut eval = |x: &[f64]| {
+        evaluations.set(evaluations.get() + 1);
+        f(x)
+    };
+
+    let mut simplex: Vec<(Vec<f64>, f64)> = Vec::with_capacity(n + 1);
+    simplex.push((x0.to_vec(), eval(x0)));
+    for i in 0..n {
+        let mut x = x0.to_vec();
+        x[i] += step;
+        let value = eval(&x);
+        simplex.push((x, value));
+    }
+
+    loop {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let best = simplex[0].1;
+        let worst = simplex[n].1;
+        if evaluations.get() >= max_evaluations || n == 0 || (worst - best).abs() <= tolerance {
+            break;
+        }
+
+        // centroid of every vertex but the worst
+        let mut centroid = vec![0.0; n];
+        for (x, _) in &simplex[..n] {
+            for (c, x) in centroid.iter_mut().zip(x) {
+                *c += x / n as f64;
+            }
+        }
+        let toward = |coefficient: f64, x: &[f64]| -> Vec<f64> {
+            centroid
+                .iter()
+                .zip(x)
+                .map(|(c, x)| c + coefficient * (c - x))
+                .collect()
+        };
+
+        let reflected = toward(REFLECT, &simplex[n].0);
+        let reflected_value = eval(&reflected);
+        if reflected_value < best {
+            let expanded = toward(EXPAND, &simplex[n].0);
+            let expanded_value = eval(&expanded);
+            simplex[n] = if expanded_value < reflected_value {
+                (expanded, expanded_value)
+            } else {
+                (reflected, reflected_value)
+            };
+        } else if reflected_value < simplex[n - 1].1 {
+            simplex[n] = (reflected, reflected_value);
+        } else {
+            let contracted = if reflected_value < worst {
+                toward(CONTRACT, &simplex[n].0)
+            } else {
+                toward(-CONTRACT, &simplex[n].0)
+            };
+            let contracted_value = eval(&contracted);
+            if contracted_value < worst.min(reflected_value) {
+                simplex[n] = (contracted, contracted_value);
+            } else {
+                let origin = simplex[0].0.clone();
+                for (x, value) in &mut simplex[1..] {
+                    for (x, o) in x.iter_mut().zip(&origin) {
+                        *x = o + SHRINK * (*x - o);
+                    }
+                    *value = eval(x);
+                }
+            }
+        }
+    }
+
+    let (x, value) = simplex.swap_remove(0);
+    Minimum {
+        x,
+        value,
+        evaluations: evaluations.get(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::nelder_mead;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_quadratic() {
+        let min = nelder_mead(
+            |x| (x[0] - 1.0).powi(2) + 2.0 * (x[1] + 0.5).powi(2),
+            &[0.0, 0.0],
+            0.1,
+            1000,
+            1e-12,
+        );
+        assert_approx_eq!(min.x[0], 1.0, 1e-4);
+        assert_approx_eq!(min.x[1], -0.5, 1e-4);
+        assert!(min.evaluations <= 1000);
+    }
+
+    #[test]
+    fn test_rejected_region() {
+        // the unconstrained minimum is at x = -1, but negative x is rejected
+        let min = nelder_mead(
+            |x| {
+                if x[0] < 0.0 {
+                    f64::INFINITY
+                } else {
+                    (x[0] + 1.0).powi(2)
+                }
+            },
+            &[2.0],
+            0.5,
+            1000,
+            1e-12,
+        );
+        assert!(min.x[0] >= 0.0);
+        assert_approx_eq!(min.x[0], 0.0, 1e-4);
+    }
+}
@@ -1,13 +1,24 @@
+use crate::box_score::GameResult;
 use crate::database::{Database, Player};
-use crate::pitch::Pitch;
-use crate::util::{fix, halfuuid, AwayHome};
+use crate::event::{GameEvent, GameObserver};
+use crate::feed::Play;
+use crate::params::ModelParams;
+use crate::pitch::{DefaultModel, Pitch, PitchModel};
+use crate::rules::Rules;
+use crate::util::{halfuuid, AwayHome};
+use crate::weather::Weather;
+use anyhow::{anyhow, bail, Result};
 use rand::{rngs::StdRng, Rng, SeedableRng};
-use serde::Deserialize;
-use std::convert::TryInto;
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use tracing::{instrument, trace};
 use uuid::Uuid;
 
+/// A game record from `game-data`.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Game {
@@ -17,17 +28,115 @@ pub struct Game {
     pub day: u8,
     pub away_pitcher: Uuid,
     pub away_team: Uuid,
-    pub away_odds: f64,
-    pub away_score: u16,
+    /// The official odds; missing from a schedule that doesn't have them yet. See
+    /// [`Game::official_odds`].
+    pub away_odds: Option<f64>,
     pub home_pitcher: Uuid,
     pub home_team: Uuid,
-    pub home_score: u16,
+    #[serde(default)]
+    pub is_postseason: bool,
+    #[serde(default)]
+    pub weather: Weather,
+    #[serde(flatten)]
+    pub rules: Rules,
+    /// Where the game stood when the record was archived: the final score for completed games,
+    /// and the start of the game for ones that haven't been played.
+    #[serde(flatten)]
+    pub snapshot: Snapshot,
 }
 
+/// The state of a game in progress, as recorded in every game update. See
+/// [`Playable::simulate_from`].
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Snapshot {
+    /// Zero for the first inning.
+    pub inning: u8,
+    pub top_of_inning: bool,
+    pub half_inning_outs: u8,
+    pub at_bat_balls: u8,
+    pub at_bat_strikes: u8,
+    /// The base each of `base_runners` is on, numbered from 0 for first.
+    pub bases_occupied: Vec<usize>,
+    pub base_runners: Vec<Uuid>,
+    /// The player at bat, if a plate appearance is under way.
+    pub away_batter: Option<Uuid>,
+    pub home_batter: Option<Uuid>,
+    /// How many batters the team has sent up, minus one: -1 before its first plate appearance.
+    pub away_team_batter_count: i32,
+    pub home_team_batter_count: i32,
+    /// Missing for games that haven't been played; see [`Snapshot::score`].
+    pub away_score: Option<u16>,
+    pub home_score: Option<u16>,
+    /// The ticker line describing what just happened.
+    pub last_update: String,
+    /// Set on the final update, which still shows the half-inning that ended the game.
+    pub game_complete: bool,
+}
+
+/// Where a game stands, with the batter and runners identified by their lineup position. See
+/// [`Playable::situation`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Situation {
+    /// Zero for the first inning.
+    pub inning: u8,
+    pub bottom: bool,
+    pub outs: u8,
+    pub balls: u8,
+    pub strikes: u8,
+    /// The lineup position of the runner on each base before home, if any. There are as many
+    /// bases as the batting team's [`Rules::base_count`].
+    pub bases: Vec<Option<u8>>,
+    /// The lineup position of each team's current or next batter.
+    pub batters: AwayHome<u8>,
+    pub score: AwayHome<u8>,
+    /// Whether no half-inning is under way: this is the start of the game, or of the half-inning
+    /// given by `inning` and `bottom`. That's the only time the game can be over; see
+    /// [`Rules::is_complete`].
+    pub between_half_innings: bool,
+}
+
+impl Default for Snapshot {
+    /// The start of a game.
+    fn default() -> Snapshot {
+        Snapshot {
+            inning: 0,
+            top_of_inning: true,
+            half_inning_outs: 0,
+            at_bat_balls: 0,
+            at_bat_strikes: 0,
+            bases_occupied: Vec::new(),
+            base_runners: Vec::new(),
+            away_batter: None,
+            home_batter: None,
+            away_team_batter_count: -1,
+            home_team_batter_count: -1,
+            away_score: None,
+            home_score: None,
+            last_update: String::new(),
+            game_complete: false,
+        }
+    }
+}
+
+impl Snapshot {
+    /// The score, which is 0-0 if the record doesn't have one.
+    pub fn score(&self) -> AwayHome<u16> {
+        AwayHome {
+            away: self.away_score.unwrap_or(0),
+            home: self.home_score.unwrap_or(0),
+        }
+    }
+}
+
+/// A game with its lineups and pitchers resolved, ready to simulate.
+#[derive(Clone)]
 pub struct Playable {
     pub id: Uuid,
     pub season: u16,
     pub day: u8,
+    pub weather: Weather,
+    pub rules: Rules,
     pub lineups: AwayHome<[Player; 9]>,
     pub pitchers: AwayHome<Player>,
 }
@@ -38,18 +147,43 @@ impl fmt::Debug for Playable {
             .field("id", &self.id)
             .field("season", &(self.season + 1))
             .field("day", &(self.day + 1))
-            .finish()
+            .field("weather", &self.weather)
+            .finish_non_exhaustive()
     }
 }
 
-#[derive(Debug, Default)]
+/// Where a simulated game stands. Runners are identified by their position in the batting team's
+/// lineup, so the lineups themselves can change mid-game; see [`Weather`].
+#[derive(Default)]
 pub struct State<'a> {
-    score: Score,
-    bases: [Option<&'a Player>; 3],
+    pub(crate) score: Score,
+    /// One slot for every base but home, which [`State::start_half_inning`] sizes for the batting
+    /// team's [`Rules`].
+    pub(crate) bases: Vec<Option<usize>>,
     position: AwayHome<usize>,
+    /// Where the first half-inning and plate appearance pick up, when resuming a [`Snapshot`].
+    pub(crate) outs: u8,
+    pub(crate) balls: u8,
+    pub(crate) strikes: u8,
+    observer: Option<&'a mut dyn GameObserver>,
 }
 
-#[derive(Debug, Default)]
+impl fmt::Debug for State<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State")
+            .field("score", &self.score)
+            .field("bases", &self.bases)
+            .field("position", &self.position)
+            .field("outs", &self.outs)
+            .field("balls", &self.balls)
+            .field("strikes", &self.strikes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where a game stands; after [`Playable::simulate`], the final score.
+#[derive(Debug, Default, Serialize)]
+#[allow(clippy::struct_field_names)]
 pub struct Score {
     pub inning: u8,
     pub bottom: bool,
@@ -57,17 +191,26 @@ pub struct Score {
 }
 
 impl Game {
+    /// Resolves the teams and players as they were when the game was played. Returns `None` if
+    /// any of them are missing from the database or a lineup doesn't have nine players.
     pub fn playable(&self, database: &Database) -> Option<Playable> {
+        self.playable_at(database, self.timestamp())
+    }
+
+    /// Like [`Game::playable`], but with the teams and players as of `time` (in milliseconds
+    /// since the Unix epoch). Use `u64::MAX` for the latest data, e.g. for games that haven't
+    /// been played yet.
+    pub fn playable_at(&self, database: &Database, time: u64) -> Option<Playable> {
         let teams = AwayHome {
             away: self.away_team,
             home: self.home_team,
         }
-        .map_opt(|id| database.teams.get(id).and_then(|h| h.get(self.timestamp())))?;
+        .map_opt(|id| database.teams.get(id).and_then(|h| h.get(time)))?;
         let lineups = teams.map_opt(|team| {
             let lineup = team
                 .lineup
                 .iter()
-                .map(|id| self.get_player(database, id))
+                .map(|id| self.get_player(database, id, time))
                 .collect::<Option<Vec<Player>>>()?;
             if lineup.len() == 9 {
                 let boxed_array: Box<[Player; 9]> = lineup.into_boxed_slice().try_into().ok()?;
@@ -77,47 +220,255 @@ impl Game {
             }
         })?;
         let pitchers = AwayHome {
-            away: self.get_player(database, &self.away_pitcher)?,
-            home: self.get_player(database, &self.home_pitcher)?,
+            away: self.get_player(database, &self.away_pitcher, time)?,
+            home: self.get_player(database, &self.home_pitcher, time)?,
         };
 
         Some(Playable {
             id: self.id,
             season: self.season,
             day: self.day,
+            weather: self.weather,
+            rules: self.rules,
             lineups,
             pitchers,
         })
     }
 
-    fn timestamp(&self) -> u64 {
+    /// The official odds of the away team winning, for comparing against.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record doesn't have them.
+    pub fn official_odds(&self) -> Result<f64> {
+        self.away_odds
+            .ok_or_else(|| anyhow!("game {} has no official odds", self.id))
+    }
+
+    /// Whether the away team won, for a completed game.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record is missing either team's score.
+    pub fn away_won(&self) -> Result<bool> {
+        match (self.snapshot.away_score, self.snapshot.home_score) {
+            (Some(away), Some(home)) => Ok(away > home),
+            _ => Err(anyhow!("game {} has no final score", self.id)),
+        }
+    }
+
+    /// The approximate time the game started, in milliseconds since the Unix epoch.
+    pub fn timestamp(&self) -> u64 {
         crate::time::game_time(self.season, self.day)
     }
 
-    fn get_player(&self, database: &Database, id: &Uuid) -> Option<Player> {
-        let mut player = database.players.get(id)?.get(self.timestamp())?.clone();
+    fn get_player(&self, database: &Database, id: &Uuid, time: u64) -> Option<Player> {
+        let mut player = database.players.get(id)?.get(time)?.clone();
         player.vibe_check(self.day);
         Some(player)
     }
 }
 
 impl Playable {
-    #[instrument(name = "Game::simulate")]
+    /// Simulates the game once with the [`DefaultModel`] and default parameters. The same seed
+    /// always produces the same result.
     pub fn simulate(&self, seed: u64) -> Score {
-        let mut state = State::default();
+        self.simulate_with(&DefaultModel, &ModelParams::default(), seed)
+    }
+
+    /// Simulates the game once, deciding each pitch with `model`.
+    pub fn simulate_with(&self, model: &impl PitchModel, params: &ModelParams, seed: u64) -> Score {
+        self.play(model, params, seed, State::default(), false)
+    }
+
+    /// Where the game stands at `snapshot`. A batter missing from the lineup is found by how many
+    /// batters their team has sent up instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a runner in the snapshot isn't in the batting team's lineup, or the
+    /// snapshot's score doesn't fit in a [`Score`].
+    pub fn situation(&self, snapshot: &Snapshot) -> Result<Situation> {
+        let position = |lineup: &[Player; 9], batter: Option<Uuid>, count: i32| {
+            // between plate appearances, the count is still on the last batter
+            let count = if batter.is_some() { count } else { count + 1 };
+            batter
+                .and_then(|id| lineup.iter().position(|player| player.id == id))
+                .and_then(|position| u8::try_from(position).ok())
+                .or_else(|| u8::try_from(count.rem_euclid(9)).ok())
+                .unwrap_or_default()
+        };
+
+        let mut situation = Situation {
+            inning: snapshot.inning,
+            bottom: !snapshot.top_of_inning,
+            outs: snapshot.half_inning_outs,
+            balls: snapshot.at_bat_balls,
+            strikes: snapshot.at_bat_strikes,
+            bases: vec![None; self.rules.base_count(!snapshot.top_of_inning)],
+            batters: AwayHome {
+                away: position(
+                    &self.lineups.away,
+                    snapshot.away_batter,
+                    snapshot.away_team_batter_count,
+                ),
+                home: position(
+                    &self.lineups.home,
+                    snapshot.home_batter,
+                    snapshot.home_team_batter_count,
+                ),
+            },
+            score: AwayHome {
+                away: u8::try_from(snapshot.score().away)?,
+                home: u8::try_from(snapshot.score().home)?,
+            },
+            between_half_innings: false,
+        };
+
+        // the update after a third out, like the final one, still shows the half-inning it ended
+        // with its outs cleared, so the ticker line is what tells them apart
+        let play = Play::parse(&snapshot.last_update);
+        let outs = if situation.bottom {
+            self.rules.outs.home
+        } else {
+            self.rules.outs.away
+        };
+        if snapshot.game_complete
+            || situation.outs >= outs
+            || situation.outs == 0 && play.is_some_and(Play::is_out)
+            || play == Some(Play::GameOver)
+        {
+            situation.inning += u8::from(situation.bottom);
+            situation.bottom = !situation.bottom;
+            situation.outs = 0;
+            situation.balls = 0;
+            situation.strikes = 0;
+            situation.bases = vec![None; self.rules.base_count(situation.bottom)];
+            situation.between_half_innings = true;
+            return Ok(situation);
+        }
+        situation.between_half_innings = match play {
+            Some(Play::PlayBall | Play::HalfInningStart) => true,
+            Some(_) => false,
+            None => {
+                snapshot.last_update.trim().is_empty()
+                    && situation.outs == 0
+                    && situation.balls == 0
+                    && situation.strikes == 0
+                    && snapshot.base_runners.is_empty()
+            }
+        };
+
+        let lineup = if situation.bottom {
+            &self.lineups.home
+        } else {
+            &self.lineups.away
+        };
+        for (base, id) in snapshot.bases_occupied.iter().zip(&snapshot.base_runners) {
+            let runner = (0_u8..)
+                .zip(lineup)
+                .find(|(_, player)| player.id == *id)
+                .map(|(position, _)| position)
+                .ok_or_else(|| anyhow!("runner {} isn't in the lineup", id))?;
+            match situation.bases.get_mut(*base) {
+                Some(slot @ None) => *slot = Some(runner),
+                _ => bail!("can't put runner {} on base {}", id, base),
+            }
+        }
+        Ok(situation)
+    }
+
+    /// Like [`Playable::simulate_with`], but picks the game up where `snapshot` left off.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot can't be resumed; see [`Playable::situation`].
+    pub fn simulate_from(
+        &self,
+        snapshot: &Snapshot,
+        model: &impl PitchModel,
+        params: &ModelParams,
+        seed: u64,
+    ) -> Result<Score> {
+        let situation = self.situation(snapshot)?;
+        let state = State::resume(self, &situation);
+        Ok(self.play(model, params, seed, state, !situation.between_half_innings))
+    }
+
+    /// Like [`Playable::simulate_with`], but also keeps the box score. The same seed produces the
+    /// same game either way.
+    pub fn simulate_with_result(
+        &self,
+        model: &impl PitchModel,
+        params: &ModelParams,
+        seed: u64,
+    ) -> GameResult {
+        let mut result = GameResult::new(self);
+        result.score = self.simulate_observed(model, params, seed, &mut result);
+        result
+    }
+
+    /// Like [`Playable::simulate_with`], but passes every [`GameEvent`] to `observer` as it
+    /// happens. The same seed produces the same game either way.
+    pub fn simulate_observed(
+        &self,
+        model: &impl PitchModel,
+        params: &ModelParams,
+        seed: u64,
+        observer: &mut impl GameObserver,
+    ) -> Score {
+        let state = State {
+            observer: Some(observer),
+            ..State::default()
+        };
+        self.play(model, params, seed, state, false)
+    }
+
+    #[instrument(name = "Game::simulate", skip(model, params))]
+    fn play(
+        &self,
+        model: &impl PitchModel,
+        params: &ModelParams,
+        seed: u64,
+        mut state: State<'_>,
+        resumed: bool,
+    ) -> Score {
         let mut rng = StdRng::seed_from_u64(halfuuid(self.id).wrapping_add(seed));
+        // the weather can change who's playing, so the rosters are copied the first time it does
+        let mut game = Cow::Borrowed(self);
+        let rules = &self.rules;
 
-        while !state.is_complete() {
-            let pitcher = state.fielding(&self.pitchers);
-            let defense = state.fielding(&self.lineups);
+        // a game resumed partway through a half-inning plays it out before it can be over
+        let mut resumed = resumed;
+        while std::mem::take(&mut resumed) || !rules.is_complete(&state.score) {
+            state.start_half_inning(rules);
+            state.emit(GameEvent::HalfInningStart);
+            let (walk, strikeout) = (*state.hitting(&rules.balls), *state.hitting(&rules.strikes));
 
-            let mut outs = 0_u8;
-            while outs < 3 {
-                let batter = state.batter(&self.lineups);
-                let mut balls = 0_u8;
-                let mut strikes = 0_u8;
+            let mut outs = std::mem::take(&mut state.outs);
+            let half_inning = *state.hitting(&rules.outs);
+            'half_inning: while outs < half_inning {
+                let mut balls = std::mem::take(&mut state.balls);
+                let mut strikes = std::mem::take(&mut state.strikes);
 
                 loop {
+                    self.weather
+                        .before_pitch(&params.weather, &mut game, &mut state, &mut rng);
+                    let lineup = state.hitting(&game.lineups);
+                    let batter = &lineup[state.at_bat()];
+                    let pitcher = state.fielding(&game.pitchers);
+                    let defense = state.fielding(&game.lineups);
+
+                    if state.bases.iter().any(Option::is_some) {
+                        let left = half_inning - outs;
+                        outs += state.steal_bases(lineup, defense, params, left, &mut rng);
+                        self.weather.after_scoring(&mut state);
+                        if outs >= half_inning {
+                            // the batter stays up, to lead off the next half-inning
+                            break 'half_inning;
+                        }
+                    }
+
                     trace!(
                         balls,
                         strikes,
@@ -130,34 +481,48 @@ impl Playable {
                         bases = ?state.bases,
                     );
 
-                    match Pitch::simulate(pitcher, batter, defense, &mut rng) {
+                    let pitch = model.pitch(params, pitcher, batter, defense, &mut rng);
+                    match pitch {
+                        Pitch::Ball => balls += 1,
+                        Pitch::Strike => strikes += 1,
+                        Pitch::Foul if strikes + 1 < strikeout => strikes += 1,
+                        _ => {}
+                    }
+                    state.emit(GameEvent::Pitch {
+                        pitcher,
+                        batter,
+                        pitch,
+                        balls,
+                        strikes,
+                    });
+
+                    match pitch {
                         Pitch::Ball => {
-                            balls += 1;
-                            if balls == 4 {
-                                state.walk(batter);
+                            if balls >= walk {
+                                state.emit(GameEvent::Walk { batter });
+                                state.walk(lineup);
                                 break;
                             }
                         }
                         Pitch::Strike => {
-                            strikes += 1;
-                            if strikes == 3 {
+                            if strikes >= strikeout {
+                                state.emit(GameEvent::Strikeout { batter, pitcher });
                                 outs += 1;
                                 break;
                             }
                         }
-                        Pitch::Foul => {
-                            if strikes < 2 {
-                                strikes += 1;
-                            }
-                        }
+                        Pitch::Foul => {}
                         Pitch::Out => {
                             outs += 1;
                             if state.bases.iter().any(Option::is_some) {
                                 let first_defender = &defense[rng.gen_range(0, 9)];
                                 let double_play = {
                                     let second_defender = &defense[rng.gen_range(0, 9)];
-                                    let p = fix(first_defender.defense(), 0.0, 0.075)
-                                        + fix(second_defender.defense(), 0.0, 0.075);
+                                    let p = params
+                                        .double_play
+                                        .defense
+                                        .fix(first_defender.defense())
+                                        + params.double_play.defense.fix(second_defender.defense());
                                     let r: f64 = rng.gen();
                                     trace!(
                                         double_play = r < p,
@@ -174,13 +539,21 @@ impl Playable {
                                     // runner on the highest base is out, batter is out, everyone
                                     // else advances 0-1 bases
                                     outs += 1;
-                                    *state.bases.iter_mut().rev().next().unwrap() = None;
-                                    state.advance(0, 1, &mut rng);
+                                    let runner = state.lead_runner_out();
+                                    state.emit(GameEvent::DoublePlay {
+                                        batter,
+                                        runner: runner.map(|runner| &lineup[runner]),
+                                    });
+                                    // nobody drives in a run by hitting into a double play
+                                    state.advance(lineup, params, 0, 1, false, &mut rng);
                                     break;
                                 }
 
                                 let fielders_choice = {
-                                    let p = fix(first_defender.defense(), 0.0, 0.75);
+                                    let p = params
+                                        .fielders_choice
+                                        .defense
+                                        .fix(first_defender.defense());
                                     let r: f64 = rng.gen();
                                     trace!(
                                         fielders_choice = r < p,
@@ -195,55 +568,245 @@ impl Playable {
                                     // runner on the highest base is out, everyone else advances
                                     // 1 base, runner on first
                                     outs += 1;
-                                    *state.bases.iter_mut().rev().next().unwrap() = None;
-                                    state.advance(1, 1, &mut rng);
-                                    state.bases[0] = Some(batter);
+                                    let runner = state.lead_runner_out();
+                                    state.emit(GameEvent::FieldersChoice {
+                                        batter,
+                                        runner: runner.map(|runner| &lineup[runner]),
+                                    });
+                                    state.advance(lineup, params, 1, 1, true, &mut rng);
+                                    state.reach(lineup, 0);
                                     break;
                                 }
                             }
+                            let fly = {
+                                let r: f64 = rng.gen();
+                                trace!(fly = r < params.fly_out, p = %params.fly_out, %r);
+                                r < params.fly_out
+                            };
+                            state.emit(GameEvent::Out { batter, fly });
+                            if outs < half_inning {
+                                if fly {
+                                    state.tag_up(lineup, params, &mut rng);
+                                } else {
+                                    // a productive out: everyone can move up a base
+                                    state.advance(lineup, params, 0, 1, true, &mut rng);
+                                }
+                            }
                             break;
                         }
                         Pitch::Single => {
-                            state.advance(1, 2, &mut rng);
-                            state.bases[0] = Some(batter);
+                            state.emit(GameEvent::Hit { batter, bases: 1 });
+                            state.advance(lineup, params, 1, 2, true, &mut rng);
+                            state.reach(lineup, 0);
                             break;
                         }
                         Pitch::Double => {
-                            state.advance(2, 3, &mut rng);
-                            state.bases[1] = Some(batter);
+                            state.emit(GameEvent::Hit { batter, bases: 2 });
+                            state.advance(lineup, params, 2, 3, true, &mut rng);
+                            state.reach(lineup, 1);
                             break;
                         }
                         Pitch::Triple => {
-                            state.advance(3, 3, &mut rng);
-                            state.bases[2] = Some(batter);
+                            state.emit(GameEvent::Hit { batter, bases: 3 });
+                            state.advance(lineup, params, 3, 3, true, &mut rng);
+                            state.reach(lineup, 2);
                             break;
                         }
                         Pitch::Dinger => {
-                            state.advance(3, 3, &mut rng);
+                            state.emit(GameEvent::Hit { batter, bases: 4 });
+                            let home = state.bases.len();
+                            state.advance(lineup, params, home, home, true, &mut rng);
                             trace!(player_scored = ?batter);
+                            state.reach(lineup, home);
                             break;
                         }
                     }
                 }
 
+                self.weather.after_scoring(&mut state);
                 state.next_batter();
             }
 
             state.next_half_inning();
         }
 
+        state.emit(GameEvent::GameOver);
         state.score
     }
 }
 
+/// Estimates the away team's chance of winning `game` by simulating it `simulations` times.
+///
+/// Simulation `i` uses seed `i`, so results are reproducible.
+pub fn monte_carlo(game: &Playable, simulations: u32) -> f64 {
+    monte_carlo_with(game, &DefaultModel, &ModelParams::default(), simulations)
+}
+
+/// Like [`monte_carlo`], but simulating with `model` and `params`.
+pub fn monte_carlo_with<M: PitchModel + Sync>(
+    game: &Playable,
+    model: &M,
+    params: &ModelParams,
+    simulations: u32,
+) -> f64 {
+    let away_wins: u32 = (0..simulations)
+        .into_par_iter()
+        .map(|i| {
+            let score = game.simulate_with(model, params, u64::from(i));
+            u32::from(score.score.away > score.score.home)
+        })
+        .sum();
+    f64::from(away_wins) / f64::from(simulations)
+}
+
+/// How a game's final score came out over many simulations; see [`score_distribution`].
+#[derive(Debug, Serialize)]
+pub struct ScoreDistribution {
+    pub simulations: u32,
+    /// The mean runs scored by each team.
+    pub expected: AwayHome<f64>,
+    /// `runs.away[r]` is the fraction of simulations where the away team scored `r` runs.
+    pub runs: AwayHome<Vec<f64>>,
+    /// Every final score that came up, most likely first.
+    pub final_scores: Vec<FinalScore>,
+}
+
+/// A final score and the fraction of simulations that ended with it.
+#[derive(Debug, Serialize)]
+pub struct FinalScore {
+    pub away: u8,
+    pub home: u8,
+    pub probability: f64,
+}
+
+impl ScoreDistribution {
+    /// The fraction of simulations the away team won.
+    pub fn away_odds(&self) -> f64 {
+        self.final_scores
+            .iter()
+            .filter(|score| score.away > score.home)
+            .fold(0.0, |odds, score| odds + score.probability)
+    }
+}
+
+/// Simulates `game` `simulations` times, like [`monte_carlo_with`], and tallies the final scores.
+pub fn score_distribution<M: PitchModel + Sync>(
+    game: &Playable,
+    model: &M,
+    params: &ModelParams,
+    simulations: u32,
+) -> ScoreDistribution {
+    let scores: Vec<AwayHome<u8>> = (0..simulations)
+        .into_par_iter()
+        .map(|i| game.simulate_with(model, params, u64::from(i)).score)
+        .collect();
+    ScoreDistribution::tally(&scores, simulations)
+}
+
+/// Like [`score_distribution`], but picking the game up where `snapshot` left off, which gives
+/// live win probabilities for a game in progress.
+///
+/// # Errors
+///
+/// Returns an error if the snapshot can't be resumed; see [`Playable::situation`].
+pub fn score_distribution_from<M: PitchModel + Sync>(
+    game: &Playable,
+    snapshot: &Snapshot,
+    model: &M,
+    params: &ModelParams,
+    simulations: u32,
+) -> Result<ScoreDistribution> {
+    let scores = (0..simulations)
+        .into_par_iter()
+        .map(|i| {
+            Ok(game
+                .simulate_from(snapshot, model, params, u64::from(i))?
+                .score)
+        })
+        .collect::<Result<Vec<AwayHome<u8>>>>()?;
+    Ok(ScoreDistribution::tally(&scores, simulations))
+}
+
+impl ScoreDistribution {
+    fn tally(scores: &[AwayHome<u8>], simulations: u32) -> ScoreDistribution {
+        let n = f64::from(simulations);
+        let tally = |team: fn(&AwayHome<u8>) -> u8| {
+            let mut counts = Vec::new();
+            for score in scores {
+                let r = usize::from(team(score));
+                if counts.len() <= r {
+                    counts.resize(r + 1, 0);
+                }
+                counts[r] += 1;
+            }
+            counts
+                .into_iter()
+                .map(|count| f64::from(count) / n)
+                .collect::<Vec<_>>()
+        };
+        let runs = AwayHome {
+            away: tally(|score| score.away),
+            home: tally(|score| score.home),
+        };
+
+        let mut counts: FxHashMap<(u8, u8), u32> = FxHashMap::default();
+        for score in scores {
+            *counts.entry((score.away, score.home)).or_default() += 1;
+        }
+
+        let mut final_scores = counts
+            .into_iter()
+            .map(|((away, home), count)| FinalScore {
+                away,
+                home,
+                probability: f64::from(count) / n,
+            })
+            .collect::<Vec<_>>();
+        final_scores.sort_by(|a, b| {
+            b.probability
+                .total_cmp(&a.probability)
+                .then((a.away, a.home).cmp(&(b.away, b.home)))
+        });
+
+        ScoreDistribution {
+            simulations,
+            expected: runs.map(|runs| (0_u8..).zip(runs).map(|(r, p)| f64::from(r) * p).sum()),
+            runs,
+            final_scores,
+        }
+    }
+}
+
 impl<'a> State<'a> {
-    fn batter<'b>(&self, lineups: &'b AwayHome<[Player; 9]>) -> &'b Player {
-        let lineup = self.hitting(lineups);
-        let position = *self.hitting(&self.position);
-        &lineup[position]
+    /// Where `game` stands in `situation`.
+    pub(crate) fn resume(game: &Playable, situation: &Situation) -> State<'a> {
+        let mut state = State {
+            score: Score {
+                inning: situation.inning,
+                bottom: situation.bottom,
+                score: situation.score,
+            },
+            bases: situation
+                .bases
+                .iter()
+                .map(|runner| runner.map(usize::from))
+                .collect(),
+            position: situation.batters.map(|position| usize::from(*position)),
+            outs: situation.outs,
+            balls: situation.balls,
+            strikes: situation.strikes,
+            ..State::default()
+        };
+        state.start_half_inning(&game.rules);
+        state
+    }
+
+    /// The lineup position of the batter who is up, or due up.
+    pub(crate) fn at_bat(&self) -> usize {
+        *self.hitting(&self.position)
     }
 
-    fn next_batter(&mut self) {
+    pub(crate) fn next_batter(&mut self) {
         let position = if self.is_top() {
             &mut self.position.away
         } else {
@@ -252,8 +815,30 @@ impl<'a> State<'a> {
         *position = (*position + 1) % 9;
     }
 
-    fn next_half_inning(&mut self) {
-        self.bases = [None; 3];
+    /// Follows a team's lineup being put in a new `order`, where `order[i]` is the old position
+    /// of the player now batting `i`th: the same players stay on base and the same batter is up.
+    pub(crate) fn reorder(&mut self, home: bool, order: &[usize; 9]) {
+        let old = |position: usize| order.iter().position(|&old| old == position).unwrap_or(0);
+        if home == self.is_bottom() {
+            for runner in self.bases.iter_mut().flatten() {
+                *runner = old(*runner);
+            }
+        }
+        let position = if home {
+            &mut self.position.home
+        } else {
+            &mut self.position.away
+        };
+        *position = old(*position);
+    }
+
+    /// Sizes the bases for the team coming up to bat, keeping anyone already on them.
+    pub(crate) fn start_half_inning(&mut self, rules: &Rules) {
+        self.bases.resize(rules.base_count(self.score.bottom), None);
+    }
+
+    pub(crate) fn next_half_inning(&mut self) {
+        self.bases.clear();
         if self.score.bottom {
             self.score.bottom = false;
             self.score.inning += 1;
@@ -262,38 +847,89 @@ impl<'a> State<'a> {
         }
     }
 
-    fn score(&mut self) {
+    /// Scores `runner`, driven in by the batter up unless they stole home.
+    fn score(&mut self, lineup: &[Player; 9], runner: usize, batted_in: bool) {
         if self.is_top() {
             self.score.score.away += 1;
         } else {
             self.score.score.home += 1;
         }
+        self.emit(GameEvent::Run {
+            runner: &lineup[runner],
+            batter: Some(&lineup[self.at_bat()]).filter(|_| batted_in),
+        });
+    }
+
+    /// Puts the batter on `base`, numbered from 0 for first, or scores them if the field doesn't
+    /// have that many bases.
+    pub(crate) fn reach(&mut self, lineup: &[Player; 9], base: usize) {
+        let at_bat = self.at_bat();
+        match self.bases.get_mut(base) {
+            Some(slot) => *slot = Some(at_bat),
+            None => self.score(lineup, at_bat, true),
+        }
     }
 
-    #[instrument]
-    fn walk(&mut self, batter: &'a Player) {
-        let mut swap = Some(batter);
-        for batter in &mut self.bases {
-            swap = std::mem::replace(batter, swap);
+    /// Takes the runner off the last base before home, the one thrown out on a double play or a
+    /// fielder's choice.
+    pub(crate) fn lead_runner_out(&mut self) -> Option<usize> {
+        self.bases.last_mut().and_then(Option::take)
+    }
+
+    pub(crate) fn emit(&mut self, event: GameEvent<'_>) {
+        if let Some(observer) = &mut self.observer {
+            observer.observe(&event, &self.score);
+        }
+    }
+
+    /// Walks the batter up in `lineup`, the batting team's.
+    #[instrument(skip(lineup))]
+    pub(crate) fn walk(&mut self, lineup: &[Player; 9]) {
+        let mut swap = Some(self.at_bat());
+        let mut forced = vec![None; self.bases.len()];
+        for (base, forced) in self.bases.iter_mut().zip(&mut forced) {
+            swap = std::mem::replace(base, swap);
             if swap.is_none() {
                 break;
             }
+            *forced = swap;
+        }
+        for (from, runner) in forced.iter().enumerate().rev() {
+            if let Some(runner) = *runner {
+                self.emit(GameEvent::Advance {
+                    runner: &lineup[runner],
+                    from,
+                    to: from + 1,
+                });
+            }
         }
-        if let Some(player) = swap {
-            trace!(player_scored = ?player);
-            self.score();
+        if let Some(runner) = swap {
+            trace!(player_scored = ?lineup[runner]);
+            self.score(lineup, runner, true);
         }
     }
 
-    #[instrument(skip(rng))]
-    fn advance(&mut self, min: usize, max: usize, rng: &mut impl Rng) {
-        let mut new_bases = [None; 3];
-        let mut score = 0_usize;
+    /// Moves every runner up at least `min` bases, and at most `max`. Runners are found in
+    /// `lineup`, the batting team's, and any who score are driven in by the batter if `batted_in`.
+    #[instrument(skip(lineup, params, rng))]
+    pub(crate) fn advance(
+        &mut self,
+        lineup: &[Player; 9],
+        params: &ModelParams,
+        min: usize,
+        max: usize,
+        batted_in: bool,
+        rng: &mut impl Rng,
+    ) {
+        let home = self.bases.len();
+        let mut new_bases = vec![None; home];
+        let mut moved = vec![None; home];
         let mut in_front = max;
         for (i, base) in self.bases.iter_mut().enumerate().rev() {
-            if let Some(runner) = base.take() {
+            if let Some(position) = base.take() {
+                let runner = &lineup[position];
                 let extra_base = if in_front > min {
-                    let p = fix(runner.baserunning(), 0.0, 0.5);
+                    let p = params.extra_base.baserunning.fix(runner.baserunning());
                     let r: f64 = rng.gen();
                     trace!(
                         extra_base = r < p,
@@ -308,21 +944,160 @@ impl<'a> State<'a> {
                 };
                 in_front = if extra_base { min + 1 } else { min };
                 let new_base = i + in_front;
-                if new_base >= 3 {
+                if new_base >= home {
                     trace!(player_scored = ?runner);
-                    score += 1;
                 } else {
-                    new_bases[new_base] = Some(runner);
+                    new_bases[new_base] = Some(position);
                 }
+                moved[i] = Some((position, new_base.min(home)));
             }
         }
-        for _ in 0..score {
-            self.score();
-        }
         self.bases = new_bases;
+        for (from, moved) in moved.iter().enumerate().rev() {
+            if let Some((runner, to)) = *moved {
+                if to > from {
+                    self.emit(GameEvent::Advance {
+                        runner: &lineup[runner],
+                        from,
+                        to,
+                    });
+                }
+                if to == home {
+                    self.score(lineup, runner, batted_in);
+                }
+            }
+        }
     }
 
-    fn hitting<'b, T>(&self, x: &'b AwayHome<T>) -> &'b T {
+    /// Gives the runner on the last base before home the chance to tag up and score on a fly out,
+    /// a sacrifice fly for the batter up. Runners are found in `lineup`, the batting team's.
+    #[instrument(skip(lineup, params, rng))]
+    pub(crate) fn tag_up(
+        &mut self,
+        lineup: &[Player; 9],
+        params: &ModelParams,
+        rng: &mut impl Rng,
+    ) {
+        let home = self.bases.len();
+        let position = match self.bases.last() {
+            Some(Some(position)) => *position,
+            _ => return,
+        };
+        let batter = &lineup[self.at_bat()];
+        let runner = &lineup[position];
+        let tag_up = {
+            let p = f64::midpoint(
+                params.sacrifice_fly.martyrdom.fix(batter.martyrdom),
+                params.sacrifice_fly.baserunning.fix(runner.baserunning()),
+            );
+            let r: f64 = rng.gen();
+            trace!(
+                tag_up = r < p,
+                %p,
+                %r,
+                batter.martyrdom = %batter.martyrdom,
+                runner.baserunning = %runner.baserunning(),
+                ?batter,
+                ?runner,
+            );
+            r < p
+        };
+        if tag_up {
+            self.bases[home - 1] = None;
+            self.emit(GameEvent::Advance {
+                runner,
+                from: home - 1,
+                to: home,
+            });
+            self.score(lineup, position, true);
+        }
+    }
+
+    /// Gives every runner with an open base ahead of them, lead runner first, the chance to try
+    /// stealing it before a pitch, against a random defender from `defense`. Returns how many were
+    /// caught stealing, stopping once that's `outs`, the outs left in the half-inning.
+    #[instrument(skip(lineup, defense, params, rng))]
+    pub(crate) fn steal_bases(
+        &mut self,
+        lineup: &[Player; 9],
+        defense: &[Player; 9],
+        params: &ModelParams,
+        outs: u8,
+        rng: &mut impl Rng,
+    ) -> u8 {
+        let mut caught = 0;
+        for base in (0..self.bases.len()).rev() {
+            let open = self.bases.get(base + 1).is_none_or(Option::is_none);
+            let position = match self.bases[base] {
+                Some(position) if open => position,
+                _ => continue,
+            };
+            let runner = &lineup[position];
+            let attempt = {
+                let p = params.steal_attempt.base_thirst.fix(runner.base_thirst);
+                let r: f64 = rng.gen();
+                trace!(
+                    steal_attempt = r < p,
+                    %p,
+                    %r,
+                    runner.base_thirst = %runner.base_thirst,
+                    ?runner,
+                );
+                r < p
+            };
+            if !attempt {
+                continue;
+            }
+
+            let defender = &defense[rng.gen_range(0, 9)];
+            let stolen = {
+                let p = f64::midpoint(
+                    params.steal_success.laserlikeness.fix(runner.laserlikeness),
+                    params.steal_success.defense.fix(1.0 - defender.defense()),
+                );
+                let r: f64 = rng.gen();
+                trace!(
+                    stolen = r < p,
+                    %p,
+                    %r,
+                    runner.laserlikeness = %runner.laserlikeness,
+                    defender.defense = %defender.defense(),
+                    ?runner,
+                    ?defender,
+                );
+                r < p
+            };
+            self.steal(lineup, base, stolen);
+            if !stolen {
+                caught += 1;
+                if caught >= outs {
+                    break;
+                }
+            }
+        }
+        caught
+    }
+
+    /// The runner on `base` steals the next one, scoring if it's home, or is caught stealing and
+    /// taken off the bases; the out is up to the caller.
+    pub(crate) fn steal(&mut self, lineup: &[Player; 9], base: usize, stolen: bool) {
+        let Some(position) = self.bases.get_mut(base).and_then(Option::take) else {
+            return;
+        };
+        let runner = &lineup[position];
+        let to = Some(base + 1).filter(|to| *to < self.bases.len());
+        if !stolen {
+            self.emit(GameEvent::CaughtStealing { runner, base: to });
+            return;
+        }
+        self.emit(GameEvent::StolenBase { runner, base: to });
+        match to {
+            Some(to) => self.bases[to] = Some(position),
+            None => self.score(lineup, position, false),
+        }
+    }
+
+    pub(crate) fn hitting<'b, T>(&self, x: &'b AwayHome<T>) -> &'b T {
         if self.is_top() {
             &x.away
         } else {
@@ -330,7 +1105,7 @@ impl<'a> State<'a> {
         }
     }
 
-    fn fielding<'b, T>(&self, x: &'b AwayHome<T>) -> &'b T {
+    pub(crate) fn fielding<'b, T>(&self, x: &'b AwayHome<T>) -> &'b T {
         if self.is_bottom() {
             &x.away
         } else {
@@ -338,111 +1113,562 @@ impl<'a> State<'a> {
         }
     }
 
-    fn is_top(&self) -> bool {
+    pub(crate) fn is_top(&self) -> bool {
         !self.score.bottom
     }
 
-    fn is_bottom(&self) -> bool {
+    pub(crate) fn is_bottom(&self) -> bool {
         self.score.bottom
     }
+}
 
-    fn is_complete(&self) -> bool {
-        if self.score.inning >= 8 {
-            if self.is_top() && self.score.score.home > self.score.score.away {
-                true
-            } else {
-                self.score.score.away != self.score.score.home
-            }
-        } else {
-            false
-        }
+/// A game for tests: [`test_lineup`]s from 0.6 away and 0.4 home, with IDs from 0 and 100, and
+/// pitchers rated 0.5 and 0.7, with IDs 200 and 201.
+///
+/// [`test_lineup`]: crate::database::test_lineup
+#[cfg(test)]
+pub(crate) fn test_game() -> Playable {
+    use crate::database::{test_lineup, test_player};
+    Playable {
+        id: Uuid::nil(),
+        season: 0,
+        day: 0,
+        weather: Weather::default(),
+        rules: Rules::default(),
+        lineups: AwayHome {
+            away: test_lineup(0, 0.6),
+            home: test_lineup(100, 0.4),
+        },
+        pitchers: AwayHome {
+            away: test_player(200, 0.5),
+            home: test_player(201, 0.7),
+        },
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unreadable_literal)]
 mod tests {
-    use super::{Player, State};
+    use super::{
+        monte_carlo_with, score_distribution, test_game, GameEvent, Player, Score, Snapshot, State,
+    };
+    use crate::database::test_player;
+    use crate::feed::test_update;
+    use crate::params::{ModelParams, Range, SacrificeFly, StealAttempt, StealSuccess};
+    use crate::pitch::DefaultModel;
+    use crate::rules::Rules;
+    use crate::util::AwayHome;
+    use assert_approx_eq::assert_approx_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+    use serde_json::json;
     use uuid::Uuid;
 
-    impl Player {
-        const fn test(uuid: u128) -> Player {
-            Player {
-                id: Uuid::from_u128(uuid),
-                name: String::new(),
-                anticapitalism: 0.0,
-                base_thirst: 0.0,
-                buoyancy: 0.0,
-                chasiness: 0.0,
-                cinnamon: 0.0,
-                coldness: 0.0,
-                continuation: 0.0,
-                divinity: 0.0,
-                ground_friction: 0.0,
-                indulgence: 0.0,
-                laserlikeness: 0.0,
-                martyrdom: 0.0,
-                moxie: 0.0,
-                musclitude: 0.0,
-                omniscience: 0.0,
-                overpowerment: 0.0,
-                patheticism: 0.0,
-                pressurization: 0.0,
-                ruthlessness: 0.0,
-                shakespearianism: 0.0,
-                tenaciousness: 0.0,
-                thwackability: 0.0,
-                tragicness: 0.0,
-                unthwackability: 0.0,
-                watchfulness: 0.0,
-            }
-        }
-    }
+    // lineup positions
+    const ANNIE: usize = 0;
+    const ALYSSA: usize = 1;
+    const EIZABETH: usize = 2;
+    const WYATT: usize = 3;
 
-    const ANNIE: &'static Player = &Player::test(0x4f7d749072814f8fb62e37e99a7c46a0);
-    const ALYSSA: &'static Player = &Player::test(0x80de2b05e0d44d3392979951b2b5c950);
-    const EIZABETH: &'static Player = &Player::test(0xaa6c266275f84506aa069a0993313216);
-    const WYATT: &'static Player = &Player::test(0xe16c3f28eecd4571be1a606bbac36b2b);
+    fn lineup() -> [Player; 9] {
+        let mut lineup: [Player; 9] = Default::default();
+        lineup[ANNIE] = test_player(0x4f7d749072814f8fb62e37e99a7c46a0, 0.0);
+        lineup[ALYSSA] = test_player(0x80de2b05e0d44d3392979951b2b5c950, 0.0);
+        lineup[EIZABETH] = test_player(0xaa6c266275f84506aa069a0993313216, 0.0);
+        lineup[WYATT] = test_player(0xe16c3f28eecd4571be1a606bbac36b2b, 0.0);
+        lineup
+    }
 
     #[test]
     fn test_walk() {
+        // Annie is up
+        let lineup = lineup();
         let mut state = State {
-            bases: [None, None, None],
+            bases: vec![None, None, None],
             ..Default::default()
         };
-        state.walk(ANNIE);
+        state.walk(&lineup);
         assert_eq!(state.bases, [Some(ANNIE), None, None]);
         assert_eq!(state.score.score.away, 0);
 
         let mut state = State {
-            bases: [Some(ALYSSA), None, None],
+            bases: vec![Some(ALYSSA), None, None],
             ..Default::default()
         };
-        state.walk(ANNIE);
+        state.walk(&lineup);
         assert_eq!(state.bases, [Some(ANNIE), Some(ALYSSA), None]);
         assert_eq!(state.score.score.away, 0);
 
         let mut state = State {
-            bases: [None, Some(ALYSSA), None],
+            bases: vec![None, Some(ALYSSA), None],
             ..Default::default()
         };
-        state.walk(ANNIE);
+        state.walk(&lineup);
         assert_eq!(state.bases, [Some(ANNIE), Some(ALYSSA), None]);
         assert_eq!(state.score.score.away, 0);
 
         let mut state = State {
-            bases: [Some(EIZABETH), Some(ALYSSA), None],
+            bases: vec![Some(EIZABETH), Some(ALYSSA), None],
             ..Default::default()
         };
-        state.walk(ANNIE);
+        state.walk(&lineup);
         assert_eq!(state.bases, [Some(ANNIE), Some(EIZABETH), Some(ALYSSA)]);
         assert_eq!(state.score.score.away, 0);
 
         let mut state = State {
-            bases: [Some(EIZABETH), Some(ALYSSA), Some(WYATT)],
+            bases: vec![Some(EIZABETH), Some(ALYSSA), Some(WYATT)],
             ..Default::default()
         };
-        state.walk(ANNIE);
+        state.walk(&lineup);
         assert_eq!(state.bases, [Some(ANNIE), Some(EIZABETH), Some(ALYSSA)]);
         assert_eq!(state.score.score.away, 1);
+
+        // with a fifth base, the runner on third is forced to fourth instead of home
+        let mut state = State {
+            bases: vec![Some(EIZABETH), Some(ALYSSA), Some(WYATT), None],
+            ..Default::default()
+        };
+        state.walk(&lineup);
+        assert_eq!(
+            state.bases,
+            [Some(ANNIE), Some(EIZABETH), Some(ALYSSA), Some(WYATT)]
+        );
+        assert_eq!(state.score.score.away, 0);
+    }
+
+    #[test]
+    fn test_advance() {
+        let lineup = lineup();
+        let params = ModelParams::default();
+        let mut runs = Vec::new();
+        let mut observer = |event: &GameEvent<'_>, _: &Score| {
+            if let GameEvent::Run { runner, batter } = *event {
+                runs.push((runner.id, batter.map(|batter| batter.id)));
+            }
+        };
+        let mut state = State {
+            bases: vec![Some(ALYSSA), None, Some(EIZABETH)],
+            observer: Some(&mut observer),
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        state.advance(&lineup, &params, 1, 1, true, &mut rng);
+        assert_eq!(state.bases, [None, Some(ALYSSA), None]);
+        state.advance(&lineup, &params, 2, 2, false, &mut rng);
+        assert_eq!(state.score.score.away, 2);
+        drop(state);
+        // Annie drives in the first run, but not the one that scores on her double play
+        assert_eq!(
+            runs,
+            [
+                (lineup[EIZABETH].id, Some(lineup[ANNIE].id)),
+                (lineup[ALYSSA].id, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_steal() {
+        let lineup = lineup();
+        let mut runs = Vec::new();
+        let mut observer = |event: &GameEvent<'_>, _: &Score| {
+            if let GameEvent::Run { runner, batter } = *event {
+                runs.push((runner.id, batter.map(|batter| batter.id)));
+            }
+        };
+        let mut state = State {
+            bases: vec![Some(ALYSSA), None, Some(EIZABETH)],
+            observer: Some(&mut observer),
+            ..Default::default()
+        };
+        state.steal(&lineup, 2, true);
+        state.steal(&lineup, 0, false);
+        assert_eq!(state.bases, [None, None, None]);
+        assert_eq!(state.score.score.away, 1);
+        drop(state);
+        // stealing home doesn't drive anyone in
+        assert_eq!(runs, [(lineup[EIZABETH].id, None)]);
+    }
+
+    #[test]
+    fn test_steal_bases() {
+        let lineup = lineup();
+        // clamped to `p` whatever the attribute
+        let always = |p: f64| Range {
+            min: p * 2.0 - 1.0,
+            max: p * 2.0,
+        };
+        let mut params = ModelParams {
+            steal_attempt: StealAttempt {
+                base_thirst: always(1.0),
+            },
+            steal_success: StealSuccess {
+                laserlikeness: always(1.0),
+                defense: always(1.0),
+            },
+            ..ModelParams::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+
+        // the lead runner goes first, which opens up the base for the one behind
+        let mut state = State {
+            bases: vec![Some(ALYSSA), Some(EIZABETH), None],
+            ..Default::default()
+        };
+        assert_eq!(state.steal_bases(&lineup, &lineup, &params, 3, &mut rng), 0);
+        assert_eq!(state.bases, [None, Some(ALYSSA), Some(EIZABETH)]);
+
+        // nobody makes it, and the half-inning ends after the first caught stealing
+        params.steal_success.laserlikeness = always(0.0);
+        params.steal_success.defense = always(0.0);
+        let mut state = State {
+            bases: vec![Some(ALYSSA), Some(EIZABETH), Some(WYATT)],
+            ..Default::default()
+        };
+        assert_eq!(state.steal_bases(&lineup, &lineup, &params, 1, &mut rng), 1);
+        assert_eq!(state.bases, [Some(ALYSSA), Some(EIZABETH), None]);
+
+        // nobody tries
+        params.steal_attempt.base_thirst = always(0.0);
+        assert_eq!(state.steal_bases(&lineup, &lineup, &params, 1, &mut rng), 0);
+        assert_eq!(state.bases, [Some(ALYSSA), Some(EIZABETH), None]);
+    }
+
+    #[test]
+    fn test_tag_up() {
+        let lineup = lineup();
+        let always = |p: f64| Range {
+            min: p * 2.0 - 1.0,
+            max: p * 2.0,
+        };
+        let mut params = ModelParams {
+            sacrifice_fly: SacrificeFly {
+                martyrdom: always(1.0),
+                baserunning: always(1.0),
+            },
+            ..ModelParams::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut runs = Vec::new();
+        let mut observer = |event: &GameEvent<'_>, _: &Score| {
+            if let GameEvent::Run { runner, batter } = *event {
+                runs.push((runner.id, batter.map(|batter| batter.id)));
+            }
+        };
+
+        // only the runner on third tags up, and the batter gets the run batted in
+        let mut state = State {
+            bases: vec![Some(ALYSSA), Some(EIZABETH), Some(WYATT)],
+            observer: Some(&mut observer),
+            ..Default::default()
+        };
+        state.tag_up(&lineup, &params, &mut rng);
+        assert_eq!(state.bases, [Some(ALYSSA), Some(EIZABETH), None]);
+        assert_eq!(state.score.score.away, 1);
+        drop(state);
+        assert_eq!(runs, [(lineup[WYATT].id, Some(lineup[ANNIE].id))]);
+
+        // nobody's on third to tag up
+        let mut state = State {
+            bases: vec![Some(ALYSSA), Some(EIZABETH), None],
+            ..Default::default()
+        };
+        state.tag_up(&lineup, &params, &mut rng);
+        assert_eq!(state.bases, [Some(ALYSSA), Some(EIZABETH), None]);
+
+        // the runner stays put
+        params.sacrifice_fly.martyrdom = always(0.0);
+        params.sacrifice_fly.baserunning = always(0.0);
+        let mut state = State {
+            bases: vec![None, None, Some(WYATT)],
+            ..Default::default()
+        };
+        state.tag_up(&lineup, &params, &mut rng);
+        assert_eq!(state.bases, [None, None, Some(WYATT)]);
+        assert_eq!(state.score.score.away, 0);
+    }
+
+    #[test]
+    fn test_reorder() {
+        let mut state = State {
+            bases: vec![Some(ANNIE), None, Some(4)],
+            position: AwayHome { away: 2, home: 7 },
+            ..Default::default()
+        };
+        // Annie bats second, the runner on third leads off, and the batter up moves to fourth
+        state.reorder(false, &[4, 0, 1, 2, 3, 5, 6, 7, 8]);
+        assert_eq!(state.bases, [Some(1), None, Some(0)]);
+        assert_eq!(state.position, AwayHome { away: 3, home: 7 });
+
+        // the fielding team's order doesn't move the runners
+        state.reorder(true, &[8, 7, 6, 5, 4, 3, 2, 1, 0]);
+        assert_eq!(state.bases, [Some(1), None, Some(0)]);
+        assert_eq!(state.position, AwayHome { away: 3, home: 1 });
+    }
+
+    #[test]
+    fn test_events() {
+        let game = test_game();
+        let params = ModelParams::default();
+        for seed in 0..10 {
+            let mut half_innings = 0;
+            let mut runs = AwayHome { away: 0, home: 0 };
+            let mut game_over = false;
+            let score = game.simulate_observed(
+                &DefaultModel,
+                &params,
+                seed,
+                &mut |event: &GameEvent<'_>, score: &Score| {
+                    assert!(!game_over);
+                    match *event {
+                        GameEvent::HalfInningStart => half_innings += 1,
+                        GameEvent::Pitch { balls, strikes, .. } => {
+                            assert!(balls <= 4 && strikes <= 3);
+                        }
+                        GameEvent::Advance { from, to, .. } => assert!(from < to && to <= 3),
+                        GameEvent::Run { .. } => {
+                            if score.bottom {
+                                runs.home += 1;
+                            } else {
+                                runs.away += 1;
+                            }
+                        }
+                        GameEvent::GameOver => game_over = true,
+                        _ => {}
+                    }
+                },
+            );
+            assert!(game_over);
+            assert_eq!(runs.away, score.score.away);
+            assert_eq!(runs.home, score.score.home);
+            assert_eq!(half_innings, score.inning * 2 + u8::from(score.bottom));
+            assert_eq!(
+                score.score.away,
+                game.simulate_with(&DefaultModel, &params, seed).score.away
+            );
+        }
+    }
+
+    #[test]
+    fn test_rules() {
+        let mut game = test_game();
+        game.rules = Rules {
+            balls: AwayHome { away: 5, home: 4 },
+            strikes: AwayHome { away: 4, home: 3 },
+            bases: AwayHome { away: 5, home: 4 },
+            innings: 5,
+            ..Rules::default()
+        };
+        let params = ModelParams::default();
+        let (mut walks, mut strikeouts, mut fourth_base) = (0, 0, 0);
+        for seed in 0..10 {
+            let mut runs = AwayHome { away: 0, home: 0 };
+            let (mut balls, mut strikes) = (0, 0);
+            let score = game.simulate_observed(
+                &DefaultModel,
+                &params,
+                seed,
+                &mut |event: &GameEvent<'_>, score: &Score| {
+                    let (walk, strikeout, home) = if score.bottom { (4, 3, 3) } else { (5, 4, 4) };
+                    match *event {
+                        GameEvent::Pitch {
+                            balls: b,
+                            strikes: s,
+                            ..
+                        } => {
+                            balls = b;
+                            strikes = s;
+                        }
+                        GameEvent::Walk { .. } => {
+                            assert_eq!(balls, walk);
+                            walks += 1;
+                        }
+                        GameEvent::Strikeout { .. } => {
+                            assert_eq!(strikes, strikeout);
+                            strikeouts += 1;
+                        }
+                        GameEvent::Advance { to, .. } => {
+                            assert!(to <= home);
+                            fourth_base += usize::from(to == 3 && home == 4);
+                        }
+                        GameEvent::Run { .. } => {
+                            if score.bottom {
+                                runs.home += 1;
+                            } else {
+                                runs.away += 1;
+                            }
+                        }
+                        _ => {}
+                    }
+                },
+            );
+            assert_eq!(runs, score.score);
+            assert!(score.inning >= 4);
+            assert!(score.score.away != score.score.home);
+        }
+        assert!(walks > 0 && strikeouts > 0 && fourth_base > 0);
+    }
+
+    #[test]
+    fn test_score_distribution() {
+        let game = test_game();
+        let params = ModelParams::default();
+        let distribution = score_distribution(&game, &DefaultModel, &params, 200);
+
+        let total: f64 = distribution
+            .final_scores
+            .iter()
+            .map(|s| s.probability)
+            .sum();
+        assert_approx_eq!(total, 1.0);
+        assert_approx_eq!(distribution.runs.away.iter().sum::<f64>(), 1.0);
+        assert!(distribution
+            .final_scores
+            .windows(2)
+            .all(|w| w[0].probability >= w[1].probability));
+        // same seeds, same games
+        assert_approx_eq!(
+            distribution.away_odds(),
+            monte_carlo_with(&game, &DefaultModel, &params, 200)
+        );
+    }
+
+    #[test]
+    fn test_completed() {
+        let game = test_update("", json!({"awayScore": 3, "homeScore": 4, "awayOdds": 0.6}));
+        assert!(!game.away_won().unwrap());
+        assert_approx_eq!(game.official_odds().unwrap(), 0.6);
+
+        // a renamed or missing field isn't a 0-0 game with no favorite
+        let game = test_update("", json!({"awayScore": 3, "homeRuns": 4}));
+        assert!(game.away_won().is_err());
+        assert!(game.official_odds().is_err());
+    }
+
+    #[test]
+    fn test_resume() {
+        let game = test_game();
+        let params = ModelParams::default();
+        for seed in 0..10 {
+            let resumed = game.simulate_from(&Snapshot::default(), &DefaultModel, &params, seed);
+            let score = game.simulate_with(&DefaultModel, &params, seed);
+            assert_eq!(resumed.unwrap().score.away, score.score.away);
+        }
+
+        // the archive keeps showing the half-inning that just ended, with its outs cleared: here
+        // the top of the ninth, after which the home team is ahead and the game is over
+        for (last_update, game_complete) in [
+            ("Game over.", true),
+            ("Jessica Telephone hit a flyout to Wyatt Quitter.", false),
+        ] {
+            let snapshot = Snapshot {
+                inning: 8,
+                top_of_inning: true,
+                away_score: Some(2),
+                home_score: Some(3),
+                away_team_batter_count: 36,
+                home_team_batter_count: 32,
+                last_update: last_update.to_owned(),
+                game_complete,
+                ..Snapshot::default()
+            };
+            let situation = game.situation(&snapshot).unwrap();
+            assert!(situation.between_half_innings);
+            assert_eq!((situation.inning, situation.bottom), (8, true));
+            let score = game
+                .simulate_from(&snapshot, &DefaultModel, &params, 0)
+                .unwrap();
+            assert_eq!((score.score.away, score.score.home), (2, 3));
+        }
+
+        // with the away team ahead, the home team still gets the bottom of the ninth
+        let snapshot = Snapshot {
+            inning: 8,
+            top_of_inning: true,
+            away_score: Some(3),
+            home_score: Some(2),
+            away_team_batter_count: 36,
+            home_team_batter_count: 32,
+            last_update: "Jessica Telephone strikes out swinging.".to_owned(),
+            ..Snapshot::default()
+        };
+        for seed in 0..10 {
+            let score = game
+                .simulate_from(&snapshot, &DefaultModel, &params, seed)
+                .unwrap();
+            assert!((score.inning, score.bottom) >= (9, false));
+        }
+
+        // a leadoff home run leaves no outs, count or runners, but the half-inning goes on
+        let snapshot = Snapshot {
+            inning: 9,
+            top_of_inning: true,
+            away_score: Some(3),
+            home_score: Some(2),
+            away_team_batter_count: 40,
+            home_team_batter_count: 36,
+            last_update: "Jessica Telephone hits a solo home run!".to_owned(),
+            ..Snapshot::default()
+        };
+        assert!(!game.situation(&snapshot).unwrap().between_half_innings);
+        for seed in 0..10 {
+            let score = game
+                .simulate_from(&snapshot, &DefaultModel, &params, seed)
+                .unwrap();
+            assert!((score.inning, score.bottom) >= (10, false));
+        }
+
+        // partway through the top of the tenth or the bottom of the ninth, the half-inning is
+        // played out, and the home team gets its turn in the tenth
+        for (inning, top_of_inning) in [(9, true), (8, false)] {
+            let snapshot = Snapshot {
+                inning,
+                top_of_inning,
+                half_inning_outs: 1,
+                away_score: Some(if top_of_inning { 3 } else { 2 }),
+                home_score: Some(if top_of_inning { 2 } else { 3 }),
+                away_team_batter_count: 40,
+                home_team_batter_count: 36,
+                ..Snapshot::default()
+            };
+            for seed in 0..10 {
+                let score = game
+                    .simulate_from(&snapshot, &DefaultModel, &params, seed)
+                    .unwrap();
+                assert!(score.inning > inning);
+                if top_of_inning {
+                    assert!((score.inning, score.bottom) >= (10, false));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_resume_runners() {
+        let mut game = test_game();
+        let params = ModelParams::default();
+        let snapshot = Snapshot {
+            bases_occupied: vec![2],
+            // nobody in either lineup
+            base_runners: vec![Uuid::from_u128(1000)],
+            ..Snapshot::default()
+        };
+        assert!(game
+            .simulate_from(&snapshot, &DefaultModel, &params, 0)
+            .is_err());
+
+        // a runner on fourth, with a fifth base; there's no fourth base without one
+        game.rules.bases.away = 5;
+        let snapshot = Snapshot {
+            bases_occupied: vec![3],
+            base_runners: vec![Uuid::from_u128(3)],
+            ..Snapshot::default()
+        };
+        let situation = game.situation(&snapshot).unwrap();
+        assert_eq!(situation.bases, [None, None, None, Some(3)]);
+        assert!(game
+            .simulate_from(&snapshot, &DefaultModel, &params, 0)
+            .is_ok());
+        game.rules.bases.away = 4;
+        assert!(game.situation(&snapshot).is_err());
     }
 }
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+/// Values of `T` keyed by the millisecond timestamp they were first observed at.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct History<T: PartialEq>(BTreeMap<u64, T>);
 
@@ -9,14 +10,16 @@ impl<T: PartialEq> History<T> {
         History(BTreeMap::new())
     }
 
+    /// The value as of `time`, i.e. the latest one observed at or before it.
     pub fn get(&self, time: u64) -> Option<&T> {
-        self.0.range(..=time).rev().next().map(|(_, v)| v)
+        self.0.range(..=time).next_back().map(|(_, v)| v)
     }
 
     pub fn insert(&mut self, time: u64, value: T) -> Option<T> {
         self.0.insert(time, value)
     }
 
+    /// Removes values that are identical to the value before them.
     pub fn dedup(&mut self) {
         // https://github.com/rust-lang/rust/issues/70530 would be nice here
         let mut prev = None;
@@ -25,7 +28,7 @@ impl<T: PartialEq> History<T> {
             if Some(v) == prev {
                 remove.push(*k);
             } else {
-                prev = Some(v)
+                prev = Some(v);
             }
         }
         for k in remove {
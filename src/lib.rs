@@ -0,0 +1,56 @@
+//! A reverse-engineered simulation of Blaseball.
+//!
+//! Load a [`Database`] of archived teams and players, turn a [`Game`] record into a [`Playable`]
+//! game by resolving its rosters and [`Rules`], then simulate it with [`Playable::simulate`] or
+//! estimate the away team's win probability with [`monte_carlo`]. Pitches are decided by a
+//! [`PitchModel`], so alternative hypotheses can be swapped in with [`Playable::simulate_with`],
+//! and every coefficient of the model lives in [`ModelParams`], including how often each
+//! [`Weather`] strikes. The [`markov`] module computes win probabilities exactly instead of by
+//! sampling. [`parse_feed`] reads the play-by-play of archived games, and [`log_likelihood`] scores
+//! a model by the pitches in it. [`Accuracy`] scores predictions against results, and
+//! [`bootstrap`] puts confidence intervals on those scores.
+
+#![warn(clippy::pedantic, rust_2018_idioms)]
+#![allow(clippy::must_use_candidate)]
+
+mod bootstrap;
+mod box_score;
+mod database;
+mod event;
+mod feed;
+pub mod fit;
+mod game;
+mod history;
+mod likelihood;
+pub mod markov;
+mod narrate;
+pub mod params;
+mod pitch;
+mod read_dir;
+mod replay;
+mod rules;
+mod stats;
+mod time;
+mod util;
+mod weather;
+
+pub use crate::bootstrap::{bootstrap, Bootstrap, Difference, Interval, Intervals, Paired};
+pub use crate::box_score::{project, BattingLine, GameResult, PitchingLine, Projection};
+pub use crate::database::{Database, Player, Team};
+pub use crate::event::{GameEvent, GameObserver};
+pub use crate::feed::{parse_feed, Feed, Play, PlayEvent, PlayState, Unparsed};
+pub use crate::game::{
+    monte_carlo, monte_carlo_with, score_distribution, score_distribution_from, FinalScore, Game,
+    Playable, Score, ScoreDistribution, Situation, Snapshot,
+};
+pub use crate::history::History;
+pub use crate::likelihood::{log_likelihood, observed_pitches, Likelihood, ObservedPitch};
+pub use crate::narrate::Narrator;
+pub use crate::params::ModelParams;
+pub use crate::pitch::{DefaultModel, Pitch, PitchModel};
+pub use crate::replay::{replay, Divergence, Mismatch, Replay};
+pub use crate::rules::{ExtraInnings, Rules};
+pub use crate::stats::Rating;
+pub use crate::time::game_time;
+pub use crate::util::{Accuracy, AwayHome};
+pub use crate::weather::Weather;
@@ -0,0 +1,138 @@
+use crate::database::Player;
+use crate::feed::Feed;
+use crate::game::Playable;
+use crate::params::ModelParams;
+use crate::pitch::{Pitch, PitchModel};
+use serde::Serialize;
+
+/// The least probability a model is taken to give any outcome, so that one pitch it thinks is
+/// impossible doesn't make the whole log-likelihood infinite.
+const MIN_PROBABILITY: f64 = 1e-6;
+
+/// A pitch from an archived game, with the players involved as they were that day.
+#[derive(Debug, Clone, Copy)]
+pub struct ObservedPitch<'a> {
+    pub pitcher: &'a Player,
+    pub batter: &'a Player,
+    pub defense: &'a [Player; 9],
+    pub pitch: Pitch,
+}
+
+/// How well a [`PitchModel`] predicts observed pitches, from [`log_likelihood`].
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Likelihood {
+    pub pitches: usize,
+    /// The natural log of the probability the model gives to every observed outcome.
+    pub log_likelihood: f64,
+    /// How many times each outcome was observed, indexed like [`Pitch::ALL`].
+    pub observed: [usize; 8],
+    /// How many times the model expected each outcome, over the same pitches.
+    pub expected: [f64; 8],
+}
+
+impl Likelihood {
+    /// The mean log-likelihood per pitch, which is comparable across data sets.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn per_pitch(&self) -> f64 {
+        self.log_likelihood / self.pitches.max(1) as f64
+    }
+}
+
+/// Finds the players behind every pitch in `feed`, a game of `game`. Returns the pitches, and how
+/// many were skipped because their batter or pitcher isn't in the game's lineups, e.g. after a
+/// mid-game substitution.
+pub fn observed_pitches<'a>(game: &'a Playable, feed: &Feed) -> (Vec<ObservedPitch<'a>>, usize) {
+    let mut observed = Vec::new();
+    let mut skipped = 0;
+    for event in &feed.events {
+        let Some(pitch) = event.play.pitch() else {
+            continue;
+        };
+        let resolved = event.pitcher.and_then(|id| {
+            let (pitcher, defense, lineup) = if id == game.pitchers.home.id {
+                (&game.pitchers.home, &game.lineups.home, &game.lineups.away)
+            } else if id == game.pitchers.away.id {
+                (&game.pitchers.away, &game.lineups.away, &game.lineups.home)
+            } else {
+                return None;
+            };
+            let batter = lineup
+                .iter()
+                .find(|player| Some(player.id) == event.batter)?;
+            Some(ObservedPitch {
+                pitcher,
+                batter,
+                defense,
+                pitch,
+            })
+        });
+        match resolved {
+            Some(pitch) => observed.push(pitch),
+            None => skipped += 1,
+        }
+    }
+    (observed, skipped)
+}
+
+/// Scores `model` by the log-likelihood of `pitches`: the higher (closer to zero), the better.
+pub fn log_likelihood<M: PitchModel>(
+    model: &M,
+    params: &ModelParams,
+    pitches: &[ObservedPitch<'_>],
+) -> Likelihood {
+    let mut likelihood = Likelihood::default();
+    for observed in pitches {
+        let distribution =
+            model.distribution(params, observed.pitcher, observed.batter, observed.defense);
+        let index = observed.pitch.index();
+        likelihood.pitches += 1;
+        likelihood.log_likelihood += distribution[index].max(MIN_PROBABILITY).ln();
+        likelihood.observed[index] += 1;
+        for (expected, p) in likelihood.expected.iter_mut().zip(&distribution) {
+            *expected += p;
+        }
+    }
+    likelihood
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{log_likelihood, ObservedPitch};
+    use crate::database::Player;
+    use crate::params::ModelParams;
+    use crate::pitch::{DefaultModel, Pitch, PitchModel};
+
+    #[test]
+    fn test_log_likelihood() {
+        let batter = Player {
+            thwackability: 0.6,
+            moxie: 0.5,
+            ..Player::default()
+        };
+        let pitcher = Player::default();
+        let defense: [Player; 9] = Default::default();
+        let params = ModelParams::default();
+        let distribution = DefaultModel.distribution(&params, &pitcher, &batter, &defense);
+
+        let observed = [Pitch::Ball, Pitch::Strike, Pitch::Single]
+            .iter()
+            .map(|pitch| ObservedPitch {
+                pitcher: &pitcher,
+                batter: &batter,
+                defense: &defense,
+                pitch: *pitch,
+            })
+            .collect::<Vec<_>>();
+        let likelihood = log_likelihood(&DefaultModel, &params, &observed);
+        assert_eq!(likelihood.pitches, 3);
+        assert_eq!(likelihood.observed[Pitch::Single.index()], 1);
+        let expected = [Pitch::Ball, Pitch::Strike, Pitch::Single]
+            .iter()
+            .map(|pitch| distribution[pitch.index()].ln())
+            .sum::<f64>();
+        assert!((likelihood.log_likelihood - expected).abs() < 1e-9);
+        assert!(likelihood.per_pitch() < 0.0);
+        assert!((likelihood.expected.iter().sum::<f64>() - 3.0).abs() < 1e-9);
+    }
+}
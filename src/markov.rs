@@ -0,0 +1,1116 @@
+//! Exact solutions of the simulation, treating it as a Markov chain.
+//!
+//! Instead of sampling games, this works out the probability of every plate appearance outcome from
+//! the ball/strike count chain over [`PitchModel::distribution`], then the distribution of runs
+//! scored in a half-inning from every base-out state, then the probability of every score
+//! difference at the end of every half-inning. It follows the same rules as
+//! [`Playable::simulate_with`], so its results should agree with [`monte_carlo_with`] up to
+//! sampling noise, except that the weather and stolen bases are ignored. Each team bats with its
+//! own balls, strikes, outs and bases from the game's [`Rules`], up to a fifth base.
+//!
+//! [`Playable::simulate_with`]: crate::Playable::simulate_with
+//! [`monte_carlo_with`]: crate::monte_carlo_with
+//! [`Rules`]: crate::Rules
+
+use crate::game::{Playable, Score, Situation};
+use crate::params::ModelParams;
+use crate::pitch::{Pitch, PitchModel};
+use crate::rules::{ExtraInnings, Rules};
+use crate::util::{chance, AwayHome};
+use anyhow::{bail, Result};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+/// Runs scored in a half-inning are capped at this; anything more is counted as this many, and a
+/// half-inning that reaches it is over (otherwise a lineup that can't make outs would never stop).
+const MAX_RUNS: usize = 40;
+/// Score differences are capped at this in either direction.
+const MAX_DIFF: i16 = 60;
+/// Paths less likely than this are dropped, and chains stop once this little probability is left.
+const EPSILON: f64 = 1e-12;
+/// Bases a runner can stand on, counting fourth. Games with more can't be solved.
+const MAX_BASES: usize = 4;
+
+/// The outcome of a plate appearance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlateAppearance {
+    Walk,
+    Strikeout,
+    Out,
+    Single,
+    Double,
+    Triple,
+    Dinger,
+}
+
+impl PlateAppearance {
+    /// Every outcome, in the order used by [`plate_appearance`].
+    pub const ALL: [PlateAppearance; 7] = [
+        PlateAppearance::Walk,
+        PlateAppearance::Strikeout,
+        PlateAppearance::Out,
+        PlateAppearance::Single,
+        PlateAppearance::Double,
+        PlateAppearance::Triple,
+        PlateAppearance::Dinger,
+    ];
+}
+
+/// The probability of each plate appearance outcome, indexed like [`PlateAppearance::ALL`], given
+/// the distribution of every pitch (indexed like [`Pitch::ALL`]).
+pub fn plate_appearance(pitch: &[f64; 8]) -> [f64; 7] {
+    plate_appearance_by_count(pitch, 4, 3)[0][0]
+}
+
+/// Outcome distributions from every count: `[balls][strikes]`.
+type Counts = Vec<Vec<[f64; 7]>>;
+
+/// Like [`plate_appearance`], but from every count, with `walk` balls for a walk and `strikeout`
+/// strikes for a strikeout.
+fn plate_appearance_by_count(pitch: &[f64; 8], walk: u8, strikeout: u8) -> Counts {
+    let (walk, strikeout) = (usize::from(walk.max(1)), usize::from(strikeout.max(1)));
+    let p = |pitch_type: Pitch| pitch[pitch_type.index()];
+    let in_play = |outcomes: &mut [f64; 7], weight: f64| {
+        outcomes[2] += weight * p(Pitch::Out);
+        outcomes[3] += weight * p(Pitch::Single);
+        outcomes[4] += weight * p(Pitch::Double);
+        outcomes[5] += weight * p(Pitch::Triple);
+        outcomes[6] += weight * p(Pitch::Dinger);
+    };
+
+    // outcomes[balls][strikes] is the outcome distribution from that count. One strike from a
+    // strikeout, a foul leaves the count alone, so that loop is summed as a geometric series.
+    let mut outcomes = vec![vec![[0.0; 7]; strikeout]; walk];
+    for balls in (0..walk).rev() {
+        for strikes in (0..strikeout).rev() {
+            let mut from = [0.0; 7];
+            if balls + 1 == walk {
+                from[0] += p(Pitch::Ball);
+            } else {
+                add(&mut from, &outcomes[balls + 1][strikes], p(Pitch::Ball));
+            }
+            if strikes + 1 == strikeout {
+                from[1] += p(Pitch::Strike);
+            } else {
+                add(&mut from, &outcomes[balls][strikes + 1], p(Pitch::Strike));
+                add(&mut from, &outcomes[balls][strikes + 1], p(Pitch::Foul));
+            }
+            in_play(&mut from, 1.0);
+            if strikes + 1 == strikeout {
+                let stay = (1.0 - p(Pitch::Foul)).max(f64::EPSILON);
+                for x in &mut from {
+                    *x /= stay;
+                }
+            }
+            outcomes[balls][strikes] = from;
+        }
+    }
+    outcomes
+}
+
+fn add(into: &mut [f64; 7], from: &[f64; 7], weight: f64) {
+    for (into, from) in into.iter_mut().zip(from) {
+        *into += weight * from;
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// Runners on each base before home, by lineup position. Bases the field doesn't have stay empty.
+type Bases = [Option<u8>; MAX_BASES];
+
+/// Everything about one team's turn at bat that doesn't change during a game.
+struct Offense {
+    /// Outs to end the half-inning.
+    outs: u8,
+    /// Bases a runner can stand on, at most [`MAX_BASES`].
+    bases: usize,
+    /// `plate_appearances[batter][balls][strikes]`
+    plate_appearances: Vec<Counts>,
+    extra_base: [f64; 9],
+    double_play: f64,
+    fielders_choice: f64,
+    fly_out: f64,
+    /// The batter's and the runner's halves of the chance to tag up on a fly out.
+    martyrdom: [f64; 9],
+    tag_up: [f64; 9],
+}
+
+/// `runs[r][j]` is the chance of scoring `r` runs in a half-inning, with lineup position `j`
+/// leading off the team's next half-inning.
+type HalfInning = Vec<[f64; 9]>;
+
+impl Offense {
+    /// The away team's offense, or the home team's if `bottom`. Fails on a field with more than
+    /// [`MAX_BASES`] bases.
+    fn new(
+        model: &impl PitchModel,
+        params: &ModelParams,
+        game: &Playable,
+        bottom: bool,
+    ) -> Result<Offense> {
+        let bases = game.rules.base_count(bottom);
+        if bases > MAX_BASES {
+            bail!(
+                "the Markov engine solves fields with at most {} bases before home, not {}",
+                MAX_BASES,
+                bases
+            );
+        }
+        let pick = |teams: &AwayHome<u8>| if bottom { teams.home } else { teams.away };
+        let (lineup, pitcher, defense) = if bottom {
+            (&game.lineups.home, &game.pitchers.away, &game.lineups.away)
+        } else {
+            (&game.lineups.away, &game.pitchers.home, &game.lineups.home)
+        };
+        let (walk, strikeout) = (pick(&game.rules.balls), pick(&game.rules.strikes));
+
+        let mut plate_appearances = Vec::with_capacity(9);
+        let mut extra_base = [0.0; 9];
+        let mut martyrdom = [0.0; 9];
+        let mut tag_up = [0.0; 9];
+        for (i, batter) in lineup.iter().enumerate() {
+            let pitch = model.distribution(params, pitcher, batter, defense);
+            plate_appearances.push(plate_appearance_by_count(&pitch, walk, strikeout));
+            extra_base[i] = chance(params.extra_base.baserunning.fix(batter.baserunning()));
+            martyrdom[i] = params.sacrifice_fly.martyrdom.fix(batter.martyrdom);
+            tag_up[i] = params.sacrifice_fly.baserunning.fix(batter.baserunning());
+        }
+
+        // Both plays pick their defenders at random. The fielder's choice is only rolled when
+        // there's no double play, and reuses the first defender.
+        let mut double_play = 0.0;
+        let mut fielders_choice = 0.0;
+        for first in defense {
+            let double_play_given_first = defense
+                .iter()
+                .map(|second| {
+                    chance(
+                        params.double_play.defense.fix(first.defense())
+                            + params.double_play.defense.fix(second.defense()),
+                    )
+                })
+                .sum::<f64>()
+                / 9.0;
+            double_play += double_play_given_first / 9.0;
+            fielders_choice += (1.0 - double_play_given_first)
+                * chance(params.fielders_choice.defense.fix(first.defense()))
+                / 9.0;
+        }
+
+        Ok(Offense {
+            outs: pick(&game.rules.outs),
+            bases,
+            plate_appearances,
+            extra_base,
+            double_play,
+            fielders_choice,
+            fly_out: chance(params.fly_out),
+            martyrdom,
+            tag_up,
+        })
+    }
+
+    /// Every way a plate appearance from the count `balls`-`strikes` can end: its probability,
+    /// then the outs, bases and runs scored afterwards.
+    fn transitions(
+        &self,
+        outs: u8,
+        bases: Bases,
+        batter: u8,
+        (balls, strikes): (u8, u8),
+    ) -> Vec<(f64, u8, Bases, usize)> {
+        let mut transitions = Vec::new();
+        let counts = &self.plate_appearances[usize::from(batter)];
+        let balls = usize::from(balls).min(counts.len() - 1);
+        let probabilities = &counts[balls][usize::from(strikes).min(counts[balls].len() - 1)];
+        for (outcome, p) in PlateAppearance::ALL.iter().zip(probabilities) {
+            if *p <= 0.0 {
+                continue;
+            }
+            match outcome {
+                PlateAppearance::Walk => {
+                    let (bases, runs) = walk(bases, self.bases, batter);
+                    transitions.push((*p, outs, bases, runs));
+                }
+                PlateAppearance::Strikeout => transitions.push((*p, outs + 1, bases, 0)),
+                PlateAppearance::Out => {
+                    if bases.iter().any(Option::is_some) {
+                        // runner on the last base is out, batter is out, everyone else advances
+                        // 0-1 bases
+                        let mut cleared = bases;
+                        cleared[self.bases - 1] = None;
+                        for (q, bases, runs) in self.advance(cleared, 0, 1) {
+                            transitions.push((p * self.double_play * q, outs + 2, bases, runs));
+                        }
+                        // runner on the last base is out, everyone else advances 1 base, runner on
+                        // first
+                        for (q, mut bases, runs) in self.advance(cleared, 1, 1) {
+                            let runs = runs + self.reach(&mut bases, 0, batter);
+                            transitions.push((p * self.fielders_choice * q, outs + 2, bases, runs));
+                        }
+                        let plain = 1.0 - self.double_play - self.fielders_choice;
+                        self.out(&mut transitions, p * plain, outs, bases, batter);
+                    } else {
+                        self.out(&mut transitions, *p, outs, bases, batter);
+                    }
+                }
+                PlateAppearance::Single | PlateAppearance::Double | PlateAppearance::Triple => {
+                    let (min, max, base) = match outcome {
+                        PlateAppearance::Single => (1, 2, 0),
+                        PlateAppearance::Double => (2, 3, 1),
+                        _ => (3, 3, 2),
+                    };
+                    for (q, mut bases, runs) in self.advance(bases, min, max) {
+                        let runs = runs + self.reach(&mut bases, base, batter);
+                        transitions.push((p * q, outs, bases, runs));
+                    }
+                }
+                PlateAppearance::Dinger => {
+                    for (q, bases, runs) in self.advance(bases, self.bases, self.bases) {
+                        transitions.push((p * q, outs, bases, runs + 1));
+                    }
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Mirrors `State::reach`: puts `batter` on `base`, or returns the run they score if the field
+    /// doesn't have that many bases.
+    fn reach(&self, bases: &mut Bases, base: usize, batter: u8) -> usize {
+        if base < self.bases {
+            bases[base] = Some(batter);
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Adds the ways an out on a ball in play, with probability `p`, can end: on a fly out the
+    /// runner on the last base may tag up and score, and on a ground out everyone may move up a
+    /// base, unless it was the last out.
+    fn out(
+        &self,
+        transitions: &mut Vec<(f64, u8, Bases, usize)>,
+        p: f64,
+        outs: u8,
+        bases: Bases,
+        batter: u8,
+    ) {
+        let outs = outs + 1;
+        if outs >= self.outs {
+            transitions.push((p, outs, bases, 0));
+            return;
+        }
+        let fly = p * self.fly_out;
+        let last = self.bases - 1;
+        match bases[last] {
+            Some(runner) => {
+                let tag_up = chance(f64::midpoint(
+                    self.martyrdom[usize::from(batter)],
+                    self.tag_up[usize::from(runner)],
+                ));
+                let mut cleared = bases;
+                cleared[last] = None;
+                transitions.push((fly * tag_up, outs, cleared, 1));
+                transitions.push((fly * (1.0 - tag_up), outs, bases, 0));
+            }
+            None => transitions.push((fly, outs, bases, 0)),
+        }
+        for (q, bases, runs) in self.advance(bases, 0, 1) {
+            transitions.push((p * (1.0 - self.fly_out) * q, outs, bases, runs));
+        }
+    }
+
+    /// Mirrors `State::advance`: every runner moves up `min` bases, or `min + 1` if they roll an
+    /// extra base and the runner ahead of them also moved more than `min`.
+    fn advance(&self, bases: Bases, min: usize, max: usize) -> Vec<(f64, Bases, usize)> {
+        let mut outcomes = vec![(1.0, [None; MAX_BASES], 0, max)];
+        for (i, runner) in bases.iter().enumerate().rev() {
+            if let Some(runner) = runner {
+                let extra_base = self.extra_base[usize::from(*runner)];
+                let mut next = Vec::with_capacity(outcomes.len() * 2);
+                for (p, new_bases, runs, in_front) in outcomes {
+                    let choices = if in_front > min {
+                        [(1.0 - extra_base, min), (extra_base, min + 1)]
+                    } else {
+                        [(1.0, min), (0.0, min + 1)]
+                    };
+                    for (q, advanced) in &choices {
+                        if *q <= 0.0 {
+                            continue;
+                        }
+                        let mut new_bases: Bases = new_bases;
+                        let mut runs = runs;
+                        if i + advanced >= self.bases {
+                            runs += 1;
+                        } else {
+                            new_bases[i + advanced] = Some(*runner);
+                        }
+                        next.push((p * q, new_bases, runs, *advanced));
+                    }
+                }
+                outcomes = next;
+            }
+        }
+        outcomes
+            .into_iter()
+            .map(|(p, bases, runs, _)| (p, bases, runs))
+            .collect()
+    }
+
+    /// The runs scored and next leadoff hitter when `leadoff` leads off a half-inning.
+    fn half_inning(&self, leadoff: u8) -> HalfInning {
+        self.half_inning_from(0, [None; MAX_BASES], leadoff, (0, 0))
+    }
+
+    /// Like [`Offense::half_inning`], but from partway through: `batter` is up with the count
+    /// at `count`, and runs are counted from here.
+    fn half_inning_from(&self, outs: u8, bases: Bases, batter: u8, count: (u8, u8)) -> HalfInning {
+        let mut result = vec![[0.0; 9]; MAX_RUNS + 1];
+        if outs >= self.outs {
+            result[0][usize::from(batter)] = 1.0;
+            return result;
+        }
+        let mut live: FxHashMap<(u8, Bases, u8), [f64; MAX_RUNS + 1]> = FxHashMap::default();
+        let mut start = [0.0; MAX_RUNS + 1];
+        start[0] = 1.0;
+        live.insert((outs, bases, batter), start);
+
+        // only the first plate appearance starts partway through the count
+        let mut count = count;
+        while live.values().flatten().sum::<f64>() > EPSILON {
+            let mut next: FxHashMap<(u8, Bases, u8), [f64; MAX_RUNS + 1]> = FxHashMap::default();
+            for ((outs, bases, batter), runs) in live {
+                let next_batter = (batter + 1) % 9;
+                for (p, outs, bases, scored) in self.transitions(outs, bases, batter, count) {
+                    for (r, q) in runs.iter().enumerate() {
+                        let q = p * q;
+                        if q < EPSILON {
+                            continue;
+                        }
+                        let r = (r + scored).min(MAX_RUNS);
+                        if outs >= self.outs || r == MAX_RUNS {
+                            result[r][usize::from(next_batter)] += q;
+                        } else {
+                            next.entry((outs, bases, next_batter))
+                                .or_insert([0.0; MAX_RUNS + 1])[r] += q;
+                        }
+                    }
+                }
+            }
+            live = next;
+            count = (0, 0);
+        }
+        result
+    }
+}
+
+/// `State::walk`, but for runners identified by lineup position, on a field with `count` bases.
+fn walk(mut bases: Bases, count: usize, batter: u8) -> (Bases, usize) {
+    let mut swap = Some(batter);
+    for base in &mut bases[..count] {
+        swap = std::mem::replace(base, swap);
+        if swap.is_none() {
+            break;
+        }
+    }
+    (bases, usize::from(swap.is_some()))
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// Each team's offense, against the other team's pitcher and defense.
+fn offenses(
+    game: &Playable,
+    model: &impl PitchModel,
+    params: &ModelParams,
+) -> Result<AwayHome<Offense>> {
+    Ok(AwayHome {
+        away: Offense::new(model, params, game, false)?,
+        home: Offense::new(model, params, game, true)?,
+    })
+}
+
+/// For each leadoff, every (runs, next leadoff, probability) worth following.
+type HalfInnings = Vec<Vec<(i16, u8, f64)>>;
+
+fn half_innings(offense: &Offense) -> HalfInnings {
+    (0..9)
+        .map(|leadoff| {
+            let mut outcomes = Vec::new();
+            for (runs, next_leadoffs) in (0..).zip(offense.half_inning(leadoff)) {
+                for (next_leadoff, q) in (0..9).zip(&next_leadoffs) {
+                    if *q >= EPSILON {
+                        outcomes.push((runs, next_leadoff, *q));
+                    }
+                }
+            }
+            outcomes
+        })
+        .collect()
+}
+
+/// The score at the end of a half-inning that ended `diff` runs apart; only the difference matters.
+fn after_half_inning(inning: u8, bottom: bool, diff: i16) -> Score {
+    let mut after = Score {
+        inning: inning.saturating_add(u8::from(bottom)),
+        bottom: !bottom,
+        ..Score::default()
+    };
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    {
+        after.score.away = diff.max(0) as u8;
+        after.score.home = (-diff).max(0) as u8;
+    }
+    after
+}
+
+/// Whether the solver follows `game` exactly, because nothing it ignores can happen: the weather
+/// has no effect and no runner ever tries to steal.
+pub fn is_exact(game: &Playable, params: &ModelParams) -> bool {
+    !game.weather.has_effect(&params.weather)
+        && game
+            .lineups
+            .away
+            .iter()
+            .chain(&game.lineups.home)
+            .all(|player| chance(params.steal_attempt.base_thirst.fix(player.base_thirst)) <= 0.0)
+}
+
+/// The exact probability that the away team wins `game`.
+///
+/// # Errors
+///
+/// Returns an error if either team bats on a field with more bases than the solver handles.
+pub fn win_probability(
+    game: &Playable,
+    model: &impl PitchModel,
+    params: &ModelParams,
+) -> Result<f64> {
+    let half_innings = offenses(game, model, params)?.map(half_innings);
+
+    // (away leadoff, home leadoff, away score - home score) at the start of each half-inning
+    let mut live: FxHashMap<(u8, u8, i16), f64> = FxHashMap::default();
+    live.insert((0, 0, 0), 1.0);
+    let mut away_wins = 0.0;
+    let mut complete = 0.0;
+
+    let mut score = Score::default();
+    while live.values().sum::<f64>() > EPSILON && score.inning < u8::MAX {
+        let mut next: FxHashMap<(u8, u8, i16), f64> = FxHashMap::default();
+        let batting_half_innings = if score.bottom {
+            &half_innings.home
+        } else {
+            &half_innings.away
+        };
+        let mut after = after_half_inning(score.inning, score.bottom, 0);
+
+        for ((away, home, diff), p) in live {
+            let leadoff = if score.bottom { home } else { away };
+            for (runs, next_leadoff, q) in &batting_half_innings[usize::from(leadoff)] {
+                let q = p * q;
+                if q < EPSILON {
+                    continue;
+                }
+                let diff = if score.bottom {
+                    diff - runs
+                } else {
+                    diff + runs
+                };
+                let diff = diff.clamp(-MAX_DIFF, MAX_DIFF);
+                after = after_half_inning(score.inning, score.bottom, diff);
+                if game.rules.is_complete(&after) {
+                    complete += q;
+                    if diff > 0 {
+                        away_wins += q;
+                    }
+                } else {
+                    let key = if score.bottom {
+                        (away, *next_leadoff, diff)
+                    } else {
+                        (*next_leadoff, home, diff)
+                    };
+                    *next.entry(key).or_default() += q;
+                }
+            }
+        }
+
+        live = next;
+        score = after;
+    }
+
+    Ok(away_wins / complete)
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// Half-innings solved backwards from the end of a game: the regulation innings, and any limited
+/// extra innings after them. A game only gets past them tied, and only when extra innings are
+/// unlimited.
+fn regulation(rules: &Rules) -> usize {
+    let innings = match rules.extra_innings {
+        ExtraInnings::Unlimited => usize::from(rules.innings),
+        ExtraInnings::Limit(extra) => usize::from(rules.innings) + usize::from(extra),
+    };
+    innings * 2
+}
+
+/// The runners in `situation`, as the solver keeps them.
+fn bases(situation: &Situation) -> Bases {
+    let mut bases = [None; MAX_BASES];
+    for (base, runner) in bases.iter_mut().zip(&situation.bases) {
+        *base = *runner;
+    }
+    bases
+}
+
+/// Every score difference, from `-MAX_DIFF` to `MAX_DIFF`.
+#[allow(clippy::cast_sign_loss)]
+const DIFFS: usize = 2 * MAX_DIFF as usize + 1;
+
+/// Partway through a half-inning: whether it's the bottom, then the outs, bases, batter and count.
+type Partial = (bool, u8, Bases, u8, (u8, u8));
+
+/// The exact probability that the away team wins from any [`Situation`] in a game. Building one
+/// solves the game backwards from its end once, after which each situation only has to play out
+/// the rest of its own half-inning.
+pub struct WinProbability {
+    rules: Rules,
+    offense: AwayHome<Offense>,
+    half_innings: AwayHome<HalfInnings>,
+    /// The away team's chance of winning from the start of each regulation half-inning, for each
+    /// away leadoff, home leadoff and score difference.
+    regulation: Vec<f64>,
+    /// The same from the start of a tied extra inning, for each away and home leadoff.
+    extras: [[f64; 9]; 9],
+    /// Half-innings already played out from partway through.
+    partial: RefCell<FxHashMap<Partial, Rc<HalfInning>>>,
+}
+
+impl WinProbability {
+    /// Solves `game` from the start of every half-inning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either team bats on a field with more bases than the solver handles.
+    pub fn new(
+        game: &Playable,
+        model: &impl PitchModel,
+        params: &ModelParams,
+    ) -> Result<WinProbability> {
+        let offense = offenses(game, model, params)?;
+        let regulation = regulation(&game.rules);
+        let mut solution = WinProbability {
+            rules: game.rules,
+            half_innings: offense.map(half_innings),
+            offense,
+            regulation: vec![0.0; regulation * 81 * DIFFS],
+            extras: [[0.0; 9]; 9],
+            partial: RefCell::default(),
+        };
+        if game.rules.extra_innings == ExtraInnings::Unlimited {
+            solution.extras = solution.extras();
+        }
+
+        for half in (0..regulation).rev() {
+            #[allow(clippy::cast_possible_truncation)]
+            let (inning, bottom) = ((half / 2) as u8, half % 2 == 1);
+            for away in 0..9 {
+                for home in 0..9 {
+                    for diff in -MAX_DIFF..=MAX_DIFF {
+                        let leadoff = if bottom { home } else { away };
+                        let value = solution.batting(bottom).1[usize::from(leadoff)]
+                            .iter()
+                            .map(|(runs, next, q)| {
+                                let (away, home, diff) = if bottom {
+                                    (away, *next, diff - runs)
+                                } else {
+                                    (*next, home, diff + runs)
+                                };
+                                q * solution.after_half_inning(inning, bottom, away, home, diff)
+                            })
+                            .sum();
+                        solution.regulation[WinProbability::index(half, away, home, diff)] = value;
+                    }
+                }
+            }
+        }
+        Ok(solution)
+    }
+
+    /// The chance of winning from the start of a game.
+    pub fn at_start(&self) -> f64 {
+        self.regulation[WinProbability::index(0, 0, 0, 0)]
+    }
+
+    /// The chance of winning from `situation`.
+    pub fn at(&self, situation: &Situation) -> f64 {
+        let score = Score {
+            inning: situation.inning,
+            bottom: situation.bottom,
+            score: situation.score,
+        };
+        if situation.between_half_innings && self.rules.is_complete(&score) {
+            return f64::from(u8::from(score.score.away > score.score.home));
+        }
+
+        let diff = i16::from(score.score.away) - i16::from(score.score.home);
+        let batter = if situation.bottom {
+            situation.batters.home
+        } else {
+            situation.batters.away
+        };
+        let key = (
+            situation.bottom,
+            situation.outs,
+            bases(situation),
+            batter,
+            (situation.balls, situation.strikes),
+        );
+        let half_inning = Rc::clone(self.partial.borrow_mut().entry(key).or_insert_with(|| {
+            Rc::new(
+                self.batting(situation.bottom)
+                    .0
+                    .half_inning_from(key.1, key.2, key.3, key.4),
+            )
+        }));
+
+        let mut value = 0.0;
+        for (runs, next_batters) in (0_i16..).zip(half_inning.iter()) {
+            for (next, q) in (0..9).zip(next_batters) {
+                if *q > 0.0 {
+                    let (away, home, diff) = if situation.bottom {
+                        (situation.batters.away, next, diff - runs)
+                    } else {
+                        (next, situation.batters.home, diff + runs)
+                    };
+                    value += q * self.after_half_inning(
+                        situation.inning,
+                        situation.bottom,
+                        away,
+                        home,
+                        diff,
+                    );
+                }
+            }
+        }
+        value
+    }
+
+    /// How much the plate appearance coming up in `situation` is expected to move the away team's
+    /// chance of winning, either way. Divide by its average over many situations for a leverage
+    /// index.
+    pub fn leverage(&self, situation: &Situation) -> f64 {
+        let before = self.at(situation);
+        let (offense, _) = self.batting(situation.bottom);
+        let batter = if situation.bottom {
+            situation.batters.home
+        } else {
+            situation.batters.away
+        };
+        offense
+            .transitions(
+                situation.outs,
+                bases(situation),
+                batter,
+                (situation.balls, situation.strikes),
+            )
+            .into_iter()
+            .map(|(p, outs, bases, runs)| {
+                let mut after = Situation {
+                    outs,
+                    bases: bases[..situation.bases.len().min(MAX_BASES)].to_vec(),
+                    balls: 0,
+                    strikes: 0,
+                    between_half_innings: false,
+                    ..situation.clone()
+                };
+                let runs = u8::try_from(runs).unwrap_or(u8::MAX);
+                if situation.bottom {
+                    after.batters.home = (batter + 1) % 9;
+                    after.score.home = after.score.home.saturating_add(runs);
+                } else {
+                    after.batters.away = (batter + 1) % 9;
+                    after.score.away = after.score.away.saturating_add(runs);
+                }
+                p * (self.at(&after) - before).abs()
+            })
+            .sum()
+    }
+
+    fn batting(&self, bottom: bool) -> (&Offense, &HalfInnings) {
+        if bottom {
+            (&self.offense.home, &self.half_innings.home)
+        } else {
+            (&self.offense.away, &self.half_innings.away)
+        }
+    }
+
+    fn index(half: usize, away: u8, home: u8, diff: i16) -> usize {
+        #[allow(clippy::cast_sign_loss)]
+        let diff = (diff.clamp(-MAX_DIFF, MAX_DIFF) + MAX_DIFF) as usize;
+        ((half * 9 + usize::from(away)) * 9 + usize::from(home)) * DIFFS + diff
+    }
+
+    /// The chance of winning once a half-inning ends `diff` runs apart, with `away` and `home`
+    /// leading off next.
+    fn after_half_inning(&self, inning: u8, bottom: bool, away: u8, home: u8, diff: i16) -> f64 {
+        let diff = diff.clamp(-MAX_DIFF, MAX_DIFF);
+        let after = after_half_inning(inning, bottom, diff);
+        if self.rules.is_complete(&after) {
+            return f64::from(u8::from(diff > 0));
+        }
+        let half = usize::from(after.inning) * 2 + usize::from(after.bottom);
+        if half < regulation(&self.rules) {
+            self.regulation[WinProbability::index(half, away, home, diff)]
+        } else if after.bottom {
+            self.extra_bottom(&self.extras, away, home, diff)
+        } else {
+            self.extras[usize::from(away)][usize::from(home)]
+        }
+    }
+
+    /// The chance of winning from the start of the bottom of an extra inning, with the away team
+    /// `diff` runs ahead and `extras` the chances from the start of the next one. The home team
+    /// loses by scoring fewer than `diff`, and plays on by scoring exactly that many.
+    fn extra_bottom(&self, extras: &[[f64; 9]; 9], away: u8, home: u8, diff: i16) -> f64 {
+        self.half_innings.home[usize::from(home)]
+            .iter()
+            .map(|(runs, next, q)| {
+                q * match runs.cmp(&diff) {
+                    Ordering::Less => 1.0,
+                    Ordering::Equal => extras[usize::from(away)][usize::from(*next)],
+                    Ordering::Greater => 0.0,
+                }
+            })
+            .sum()
+    }
+
+    /// Extra innings repeat until one ends with a team ahead, so their chances are found by
+    /// iterating until they stop changing.
+    fn extras(&self) -> [[f64; 9]; 9] {
+        let mut extras = [[0.0; 9]; 9];
+        // bottom[away][home][runs]: the chance of winning once the away team scores `runs`
+        let mut bottom = vec![[[0.0; MAX_RUNS + 1]; 9]; 9];
+        for _ in 0..10_000 {
+            for (away, bottom) in (0..).zip(&mut bottom) {
+                for (home, bottom) in (0..).zip(bottom) {
+                    for (runs, bottom) in (0..).zip(bottom) {
+                        *bottom = self.extra_bottom(&extras, away, home, runs);
+                    }
+                }
+            }
+
+            let mut change: f64 = 0.0;
+            for away in 0..9_u8 {
+                for home in 0..9 {
+                    // the home team bats after whatever the away team scores
+                    let value = self.half_innings.away[usize::from(away)]
+                        .iter()
+                        .map(|(runs, next, q)| {
+                            #[allow(clippy::cast_sign_loss)]
+                            let runs = *runs as usize;
+                            q * bottom[usize::from(*next)][home][runs]
+                        })
+                        .sum::<f64>();
+                    let old = &mut extras[usize::from(away)][home];
+                    change = change.max((value - *old).abs());
+                    *old = value;
+                }
+            }
+            if change < EPSILON {
+                break;
+            }
+        }
+        extras
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_exact, plate_appearance, win_probability, Offense, PlateAppearance, WinProbability,
+    };
+    use crate::game::{monte_carlo_with, score_distribution_from, test_game, Situation, Snapshot};
+    use crate::params::ModelParams;
+    use crate::pitch::{DefaultModel, Pitch};
+    use crate::rules::{ExtraInnings, Rules};
+    use crate::util::AwayHome;
+    use assert_approx_eq::assert_approx_eq;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_plate_appearance() {
+        let mut pitch = [0.0; 8];
+        pitch[Pitch::Ball.index()] = 1.0;
+        assert_approx_eq!(plate_appearance(&pitch)[0], 1.0);
+
+        // four balls before three strikes: 0.5^4 * (1 + 4 * 0.5 + 10 * 0.5^2)
+        let mut pitch = [0.0; 8];
+        pitch[Pitch::Ball.index()] = 0.5;
+        pitch[Pitch::Strike.index()] = 0.5;
+        let outcomes = plate_appearance(&pitch);
+        assert_approx_eq!(outcomes[0], 0.343_75);
+        assert_approx_eq!(outcomes[1], 0.656_25);
+
+        // fouls can't be the third strike, so this batter always walks eventually
+        let mut pitch = [0.0; 8];
+        pitch[Pitch::Ball.index()] = 0.5;
+        pitch[Pitch::Foul.index()] = 0.5;
+        assert_approx_eq!(plate_appearance(&pitch)[0], 1.0);
+
+        let mut pitch = [0.0; 8];
+        pitch[Pitch::Ball.index()] = 0.4;
+        pitch[Pitch::Strike.index()] = 0.3;
+        pitch[Pitch::Foul.index()] = 0.2;
+        pitch[Pitch::Single.index()] = 0.1;
+        let outcomes = plate_appearance(&pitch);
+        assert_approx_eq!(outcomes.iter().sum::<f64>(), 1.0);
+        assert_approx_eq!(outcomes[PlateAppearance::Double as usize], 0.0);
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut offense = Offense {
+            outs: 3,
+            bases: 3,
+            plate_appearances: Vec::new(),
+            extra_base: [0.5; 9],
+            double_play: 0.0,
+            fielders_choice: 0.0,
+            fly_out: 0.5,
+            martyrdom: [0.5; 9],
+            tag_up: [0.5; 9],
+        };
+        // a runner on first takes an extra base on a single half the time
+        let outcomes = offense.advance([Some(0), None, None, None], 1, 2);
+        assert_eq!(outcomes.len(), 2);
+        assert_approx_eq!(outcomes[0].0, 0.5);
+        assert_eq!(outcomes[0].1, [None, Some(0), None, None]);
+        assert_eq!(outcomes[1].1, [None, None, Some(0), None]);
+        // runners can't pass the runner ahead of them
+        let outcomes = offense.advance([Some(0), Some(1), None, None], 1, 1);
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].1, [None, Some(0), Some(1), None]);
+        assert_eq!(offense.advance([None, None, Some(0), None], 1, 1)[0].2, 1);
+
+        // with a fifth base, the runner on third only gets to fourth
+        offense.bases = 4;
+        let outcomes = offense.advance([None, None, Some(0), None], 1, 1);
+        assert_eq!(outcomes[0].1, [None, None, None, Some(0)]);
+        assert_eq!(outcomes[0].2, 0);
+    }
+
+    #[test]
+    fn test_out() {
+        let offense = Offense {
+            outs: 3,
+            bases: 3,
+            plate_appearances: Vec::new(),
+            extra_base: [1.0; 9],
+            double_play: 0.0,
+            fielders_choice: 0.0,
+            fly_out: 0.5,
+            martyrdom: [0.2; 9],
+            tag_up: [0.6; 9],
+        };
+        let mut transitions = Vec::new();
+        offense.out(&mut transitions, 1.0, 0, [Some(1), None, Some(2), None], 0);
+        let scored = transitions
+            .iter()
+            .filter(|(_, _, _, runs)| *runs > 0)
+            .map(|(p, ..)| p)
+            .sum::<f64>();
+        // the runner on third tags up on 40% of fly outs, and always takes the extra base on a
+        // ground out
+        assert_approx_eq!(scored, 0.5 * 0.4 + 0.5);
+        assert_approx_eq!(transitions.iter().map(|(p, ..)| p).sum::<f64>(), 1.0);
+        assert!(transitions.iter().all(|(_, outs, ..)| *outs == 1));
+
+        // nobody moves on the third out
+        let mut transitions = Vec::new();
+        offense.out(&mut transitions, 1.0, 2, [Some(1), None, Some(2), None], 0);
+        assert_eq!(
+            transitions,
+            vec![(1.0, 3, [Some(1), None, Some(2), None], 0)]
+        );
+    }
+
+    #[test]
+    fn test_half_inning_sums_to_one() {
+        let params = ModelParams::default();
+        let offense = Offense::new(&DefaultModel, &params, &test_game(), false).unwrap();
+        for leadoff in 0..9 {
+            let total: f64 = offense.half_inning(leadoff).iter().flatten().sum();
+            // only the pruned paths are missing
+            assert_approx_eq!(total, 1.0, 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_matches_monte_carlo() {
+        // runners who never steal, so the simulation has nothing the solver ignores
+        let mut game = test_game();
+        assert!(!is_exact(&game, &ModelParams::default()));
+        for player in game.lineups.away.iter_mut().chain(&mut game.lineups.home) {
+            player.base_thirst = 0.0;
+        }
+        let params = ModelParams::default();
+        assert!(is_exact(&game, &params));
+        let exact = win_probability(&game, &DefaultModel, &params).unwrap();
+        let simulated = monte_carlo_with(&game, &DefaultModel, &params, 20000);
+        // about three standard errors
+        assert_approx_eq!(exact, simulated, 0.011);
+    }
+
+    #[test]
+    fn test_nonstandard_rules() {
+        // an away team with a fourth strike against a home team with a fifth base
+        let mut game = test_game();
+        game.rules = Rules {
+            strikes: AwayHome { away: 4, home: 3 },
+            bases: AwayHome { away: 4, home: 5 },
+            ..Rules::default()
+        };
+        let params = ModelParams::default();
+        let exact = win_probability(&game, &DefaultModel, &params).unwrap();
+        assert!(
+            (exact - win_probability(&test_game(), &DefaultModel, &params).unwrap()).abs() > 0.01
+        );
+        let simulated = monte_carlo_with(&game, &DefaultModel, &params, 2000);
+        assert_approx_eq!(exact, simulated, 0.035);
+
+        // a runner on fourth in the bottom of the ninth
+        let snapshot = Snapshot {
+            inning: 8,
+            top_of_inning: false,
+            half_inning_outs: 1,
+            bases_occupied: vec![3],
+            base_runners: vec![Uuid::from_u128(108)],
+            away_score: Some(3),
+            home_score: Some(2),
+            away_team_batter_count: 36,
+            home_team_batter_count: 33,
+            ..Snapshot::default()
+        };
+        let situation = game.situation(&snapshot).unwrap();
+        assert_eq!(situation.bases, [None, None, None, Some(8)]);
+        let solution = WinProbability::new(&game, &DefaultModel, &params).unwrap();
+        let simulated = score_distribution_from(&game, &snapshot, &DefaultModel, &params, 2000)
+            .unwrap()
+            .away_odds();
+        assert_approx_eq!(solution.at(&situation), simulated, 0.035);
+
+        // a sixth base is more than the solver handles
+        game.rules.bases.home = 6;
+        assert!(win_probability(&game, &DefaultModel, &params).is_err());
+        assert!(WinProbability::new(&game, &DefaultModel, &params).is_err());
+    }
+
+    #[test]
+    fn test_limited_extra_innings() {
+        // short games that can end tied, which count as losses for the away team
+        let mut game = test_game();
+        game.rules = Rules {
+            innings: 3,
+            extra_innings: ExtraInnings::Limit(1),
+            ..Rules::default()
+        };
+        let params = ModelParams::default();
+        let exact = win_probability(&game, &DefaultModel, &params).unwrap();
+        let solution = WinProbability::new(&game, &DefaultModel, &params).unwrap();
+        assert_approx_eq!(solution.at_start(), exact, 1e-6);
+        let simulated = monte_carlo_with(&game, &DefaultModel, &params, 2000);
+        assert_approx_eq!(exact, simulated, 0.035);
+    }
+
+    #[test]
+    fn test_win_probability_from_situation() {
+        let game = test_game();
+        let params = ModelParams::default();
+        let solution = WinProbability::new(&game, &DefaultModel, &params).unwrap();
+        let exact = win_probability(&game, &DefaultModel, &params).unwrap();
+        assert_approx_eq!(solution.at_start(), exact, 1e-6);
+        assert_approx_eq!(solution.at(&Situation::default()), exact, 1e-6);
+
+        // bottom of the seventh, home down one with a runner on second
+        let snapshot = Snapshot {
+            inning: 6,
+            top_of_inning: false,
+            half_inning_outs: 1,
+            at_bat_balls: 2,
+            bases_occupied: vec![1],
+            base_runners: vec![Uuid::from_u128(108)],
+            away_score: Some(4),
+            home_score: Some(3),
+            away_team_batter_count: 30,
+            home_team_batter_count: 27,
+            ..Snapshot::default()
+        };
+        let situation = game.situation(&snapshot).unwrap();
+        let simulated = score_distribution_from(&game, &snapshot, &DefaultModel, &params, 2000)
+            .unwrap()
+            .away_odds();
+        assert_approx_eq!(solution.at(&situation), simulated, 0.035);
+
+        // partway through the top of the tenth, the home team still gets its turn
+        let snapshot = Snapshot {
+            inning: 9,
+            half_inning_outs: 1,
+            away_score: Some(3),
+            home_score: Some(2),
+            away_team_batter_count: 40,
+            home_team_batter_count: 36,
+            ..Snapshot::default()
+        };
+        let extra = game.situation(&snapshot).unwrap();
+        let simulated = score_distribution_from(&game, &snapshot, &DefaultModel, &params, 2000)
+            .unwrap()
+            .away_odds();
+        assert!(solution.at(&extra) < 1.0);
+        assert_approx_eq!(solution.at(&extra), simulated, 0.035);
+
+        // as it does after a leadoff home run in the top of the tenth, with no outs or runners
+        let leadoff = game
+            .situation(&Snapshot {
+                inning: 9,
+                away_score: Some(3),
+                home_score: Some(2),
+                away_team_batter_count: 40,
+                home_team_batter_count: 36,
+                last_update: "Jessica Telephone hits a solo home run!".to_owned(),
+                ..Snapshot::default()
+            })
+            .unwrap();
+        assert!(!leadoff.between_half_innings);
+        assert!(solution.at(&leadoff) < 1.0);
+
+        // and a walk-off in the bottom of the tenth ends the game with no outs or runners either
+        let over = game
+            .situation(&Snapshot {
+                inning: 9,
+                top_of_inning: false,
+                away_score: Some(3),
+                home_score: Some(4),
+                away_team_batter_count: 40,
+                home_team_batter_count: 36,
+                last_update: "Game over.".to_owned(),
+                game_complete: true,
+                ..Snapshot::default()
+            })
+            .unwrap();
+        assert_approx_eq!(solution.at(&over), 0.0, 1e-9);
+
+        // the same spot matters more than the first inning of a blowout
+        let blowout = Situation {
+            score: AwayHome { away: 10, home: 0 },
+            ..Situation::default()
+        };
+        assert!(solution.leverage(&situation) > solution.leverage(&blowout));
+    }
+}
@@ -0,0 +1,268 @@
+use crate::event::{GameEvent, GameObserver};
+use crate::game::Score;
+use crate::pitch::Pitch;
+use crate::util::AwayHome;
+use uuid::Uuid;
+
+/// Turns the events of a simulated game into Blaseball-style ticker lines, like
+/// "Jessica Telephone hits a Double!" or "Ball. 2-1.".
+#[derive(Debug, Default)]
+pub struct Narrator {
+    teams: AwayHome<String>,
+    /// The batter we've announced, until their plate appearance ends.
+    batter: Option<Uuid>,
+    /// The line for the last ball or strike, held back until we know it didn't end the plate
+    /// appearance; how many it takes depends on the game's rules.
+    count: Option<String>,
+    pub lines: Vec<String>,
+}
+
+impl Narrator {
+    /// `teams` are the names used in the ticker, e.g. "Tigers".
+    pub fn new(teams: AwayHome<String>) -> Narrator {
+        Narrator {
+            teams,
+            ..Narrator::default()
+        }
+    }
+
+    fn batting(&self, score: &Score) -> &str {
+        if score.bottom {
+            &self.teams.home
+        } else {
+            &self.teams.away
+        }
+    }
+
+    fn say(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    /// Says the held-back ball or strike, unless `event` ends the plate appearance with it; the
+    /// pitch that does is narrated by the event that follows.
+    fn say_count(&mut self, event: &GameEvent<'_>) {
+        if let Some(line) = self.count.take() {
+            if !matches!(event, GameEvent::Walk { .. } | GameEvent::Strikeout { .. }) {
+                self.say(line);
+            }
+        }
+    }
+
+    /// The ticker line for something that happens between pitches: a steal, or the weather.
+    fn between_pitches(&self, event: &GameEvent<'_>, score: &Score) -> Option<String> {
+        match *event {
+            GameEvent::StolenBase { runner, base } => {
+                Some(format!("{} steals {}!", runner.name, base_name(base)))
+            }
+            GameEvent::CaughtStealing { runner, base } => Some(format!(
+                "{} gets caught stealing {}.",
+                runner.name,
+                base_name(base)
+            )),
+            GameEvent::Incineration {
+                victim,
+                replacement,
+            } => Some(format!(
+                "Rogue Umpire incinerated {}! Replaced by {}.",
+                victim.name, replacement.name
+            )),
+            GameEvent::PeanutReaction { player, allergic } => Some(format!(
+                "{} swallowed a stray peanut and had {} reaction!",
+                player.name,
+                if allergic { "an allergic" } else { "a yummy" }
+            )),
+            GameEvent::Feedback { batter, pitcher } => Some(format!(
+                "Reality flickers. {} and {} switch teams!",
+                batter.name, pitcher.name
+            )),
+            GameEvent::Reverb { home } => {
+                let team = if home {
+                    &self.teams.home
+                } else {
+                    &self.teams.away
+                };
+                Some(format!(
+                    "Reverberations are at dangerous levels! The {team} were shuffled."
+                ))
+            }
+            GameEvent::Blooddrain {
+                drainer,
+                victim,
+                rating,
+            } => Some(format!(
+                "The Blooddrain gurgled! {} siphoned some of {}'s {} ability!",
+                drainer.name, victim.name, rating
+            )),
+            GameEvent::Sun2 => Some(format!(
+                "The Sun 2 smiles upon the {}. {} {}, {} {}.",
+                self.batting(score),
+                self.teams.away,
+                score.score.away,
+                self.teams.home,
+                score.score.home
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl GameObserver for Narrator {
+    fn observe(&mut self, event: &GameEvent<'_>, score: &Score) {
+        self.say_count(event);
+        match *event {
+            GameEvent::HalfInningStart => {
+                let half = if score.bottom { "Bottom" } else { "Top" };
+                let line = format!(
+                    "{} of {}, {} batting.",
+                    half,
+                    score.inning + 1,
+                    self.batting(score)
+                );
+                self.say(line);
+            }
+            GameEvent::Pitch {
+                batter,
+                pitch,
+                balls,
+                strikes,
+                ..
+            } => {
+                if self.batter != Some(batter.id) {
+                    self.batter = Some(batter.id);
+                    let line = format!("{} batting for the {}.", batter.name, self.batting(score));
+                    self.say(line);
+                }
+                match pitch {
+                    Pitch::Ball => self.count = Some(format!("Ball. {balls}-{strikes}.")),
+                    Pitch::Strike => self.count = Some(format!("Strike. {balls}-{strikes}.")),
+                    Pitch::Foul => self.say(format!("Foul Ball. {balls}-{strikes}.")),
+                    _ => {}
+                }
+            }
+            GameEvent::Walk { batter } => {
+                self.batter = None;
+                self.say(format!("{} draws a walk.", batter.name));
+            }
+            GameEvent::Strikeout { batter, .. } => {
+                self.batter = None;
+                self.say(format!("{} strikes out.", batter.name));
+            }
+            GameEvent::Out { batter, fly } => {
+                self.batter = None;
+                let out = if fly { "a flyout" } else { "a ground out" };
+                self.say(format!("{} hit {}.", batter.name, out));
+            }
+            GameEvent::DoublePlay { batter, runner } => {
+                self.batter = None;
+                self.say(format!("{} hit into a double play!", batter.name));
+                if let Some(runner) = runner {
+                    self.say(format!("{} out at home.", runner.name));
+                }
+            }
+            GameEvent::FieldersChoice { batter, runner } => {
+                self.batter = None;
+                self.say(format!("{} reaches on fielder's choice.", batter.name));
+                if let Some(runner) = runner {
+                    self.say(format!("{} out at home.", runner.name));
+                }
+            }
+            GameEvent::Hit { batter, bases } => {
+                self.batter = None;
+                let line = match bases {
+                    1 => format!("{} hits a Single!", batter.name),
+                    2 => format!("{} hits a Double!", batter.name),
+                    3 => format!("{} hits a Triple!", batter.name),
+                    _ => format!("{} hits a home run!", batter.name),
+                };
+                self.say(line);
+            }
+            GameEvent::Run { runner, .. } => {
+                let line = format!(
+                    "{} scores! {} {}, {} {}.",
+                    runner.name,
+                    self.teams.away,
+                    score.score.away,
+                    self.teams.home,
+                    score.score.home
+                );
+                self.say(line);
+            }
+            GameEvent::Advance { .. } => {}
+            GameEvent::StolenBase { .. }
+            | GameEvent::CaughtStealing { .. }
+            | GameEvent::Incineration { .. }
+            | GameEvent::PeanutReaction { .. }
+            | GameEvent::Feedback { .. }
+            | GameEvent::Reverb { .. }
+            | GameEvent::Blooddrain { .. }
+            | GameEvent::Sun2 => {
+                if let Some(line) = self.between_pitches(event, score) {
+                    self.say(line);
+                }
+            }
+            GameEvent::GameOver => {
+                let line = format!(
+                    "Game over. {} {}, {} {}.",
+                    self.teams.away, score.score.away, self.teams.home, score.score.home
+                );
+                self.say(line);
+            }
+        }
+    }
+}
+
+/// A base as the ticker names it, numbered from 0 for first, or home if `None`.
+fn base_name(base: Option<usize>) -> String {
+    match base {
+        None => "home".to_string(),
+        Some(0) => "first base".to_string(),
+        Some(1) => "second base".to_string(),
+        Some(2) => "third base".to_string(),
+        Some(3) => "fourth base".to_string(),
+        Some(base) => format!("base {}", base + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Narrator;
+    use crate::game::test_game;
+    use crate::params::ModelParams;
+    use crate::pitch::DefaultModel;
+    use crate::util::AwayHome;
+
+    #[test]
+    fn test_narrate() {
+        let mut game = test_game();
+        for (team, lineup) in [
+            ("Away", &mut game.lineups.away),
+            ("Home", &mut game.lineups.home),
+        ] {
+            for (i, player) in lineup.iter_mut().enumerate() {
+                player.name = format!("{team} {i}");
+            }
+        }
+
+        let mut narrator = Narrator::new(AwayHome {
+            away: "Tigers".to_string(),
+            home: "Crabs".to_string(),
+        });
+        let score =
+            game.simulate_observed(&DefaultModel, &ModelParams::default(), 0, &mut narrator);
+        assert_eq!(narrator.lines[0], "Top of 1, Tigers batting.");
+        assert_eq!(narrator.lines[1], "Away 0 batting for the Tigers.");
+        assert_eq!(
+            narrator.lines.last().unwrap(),
+            &format!(
+                "Game over. Tigers {}, Crabs {}.",
+                score.score.away, score.score.home
+            )
+        );
+        let runs = narrator
+            .lines
+            .iter()
+            .filter(|line| line.contains(" scores! "))
+            .count();
+        assert_eq!(runs, usize::from(score.score.away + score.score.home));
+    }
+}
@@ -0,0 +1,511 @@
+use crate::util::fix;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Every tunable coefficient in the simulation.
+///
+/// Each pipeline step is named after the roll it controls, and each attribute-driven term is a
+/// [`Range`] named after the attribute. Fields missing from a params file keep their defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelParams {
+    pub strike_zone: StrikeZone,
+    pub swing: Swing,
+    pub contact: Contact,
+    /// Chance that a ball put in play is foul.
+    pub foul: f64,
+    pub home_run: HomeRun,
+    pub out: Out,
+    /// Chance an out on a ball in play is caught in the air; the rest are ground outs.
+    pub fly_out: f64,
+    pub sacrifice_fly: SacrificeFly,
+    pub single: Single,
+    pub triple: Triple,
+    pub extra_base: ExtraBase,
+    pub double_play: DoublePlay,
+    pub fielders_choice: FieldersChoice,
+    pub steal_attempt: StealAttempt,
+    pub steal_success: StealSuccess,
+    pub weather: WeatherEffects,
+}
+
+/// Maps an attribute onto a probability linearly: 0 becomes `min`, 1 becomes `max`, and the
+/// result is clamped to 0..=1.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Range {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Range {
+    const fn new(min: f64, max: f64) -> Range {
+        Range { min, max }
+    }
+
+    pub fn fix(self, x: f64) -> f64 {
+        fix(x, self.min, self.max)
+    }
+}
+
+/// Chance the pitch is in the strike zone: the mean of the batter's (inverted) moxie and the
+/// pitcher's rating.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrikeZone {
+    pub moxie: Range,
+    pub pitching: Range,
+}
+
+/// Chance the batter swings.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Swing {
+    pub in_zone: f64,
+    pub out_of_zone: f64,
+    pub shakespearianism: Range,
+}
+
+/// Chance a swing makes contact.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Contact {
+    pub in_zone: f64,
+    pub out_of_zone: f64,
+    pub patheticism: Range,
+    pub thwackability: Range,
+    pub unthwackability: Range,
+}
+
+/// Chance a fair ball is a home run.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HomeRun {
+    pub divinity: Range,
+}
+
+/// Chance a ball in play is fielded for an out.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Out {
+    pub defense: Range,
+    pub thwackability: Range,
+}
+
+/// Chance the runner on third tags up and scores on a fly out that isn't the last out: the mean
+/// of the batter's martyrdom and the runner's rating.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SacrificeFly {
+    pub martyrdom: Range,
+    pub baserunning: Range,
+}
+
+/// Chance a hit is a single is one minus this.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Single {
+    pub musclitude: Range,
+}
+
+/// Chance a hit that isn't a single is a triple.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Triple {
+    pub ground_friction: Range,
+}
+
+/// Chance a runner takes an extra base on a hit.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExtraBase {
+    pub baserunning: Range,
+}
+
+/// Chance of a double play, summed over two random defenders.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DoublePlay {
+    pub defense: Range,
+}
+
+/// Chance of a fielder's choice when there's no double play.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FieldersChoice {
+    pub defense: Range,
+}
+
+/// Chance a runner tries to steal the base ahead of them, if it's open, before a pitch.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StealAttempt {
+    pub base_thirst: Range,
+}
+
+/// Chance a steal succeeds: the mean of the runner's laserlikeness and a random defender's
+/// (inverted) rating.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StealSuccess {
+    pub laserlikeness: Range,
+    pub defense: Range,
+}
+
+/// Chances of each weather's effect, rolled before every pitch of a game in that weather, and
+/// how big the effects are.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeatherEffects {
+    /// Chance the batter or pitcher is incinerated in a solar eclipse.
+    pub incineration: f64,
+    /// Chance the batter or pitcher swallows a peanut.
+    pub peanut: f64,
+    /// How much a peanut lowers every attribute of an allergic player, or raises them for anyone
+    /// else.
+    pub peanut_reaction: f64,
+    /// Chance the batter and pitcher swap teams in feedback.
+    pub feedback: f64,
+    /// Chance one team's lineup is shuffled in reverb.
+    pub reverb: f64,
+    /// Chance the batter or pitcher siphons from the other in a blooddrain.
+    pub blooddrain: f64,
+    /// How much a blooddrain moves the attributes behind one star rating.
+    pub siphon: f64,
+}
+
+impl ModelParams {
+    /// Reads parameters from a JSON or TOML file, chosen by its extension.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or parsed, or has an unknown extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ModelParams> {
+        let path = path.as_ref();
+        let s = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Ok(serde_json::from_str(&s)?),
+            Some("toml") => Ok(toml::from_str(&s)?),
+            _ => bail!("{} is not a .json or .toml file", path.display()),
+        }
+    }
+
+    /// Writes parameters to a JSON or TOML file, chosen by its extension.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be written or has an unknown extension.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let s = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::to_string_pretty(self)?,
+            Some("toml") => self.to_toml()?,
+            _ => bail!("{} is not a .json or .toml file", path.display()),
+        };
+        fs::write(path, s)?;
+        Ok(())
+    }
+
+    /// Formats parameters as TOML.
+    ///
+    /// # Errors
+    ///
+    /// Never, in practice; serialization is fallible in general.
+    pub fn to_toml(&self) -> Result<String> {
+        // going through `toml::Value` puts `foul` ahead of the tables, as TOML requires
+        Ok(toml::to_string(&toml::Value::try_from(self)?)?)
+    }
+
+    /// Every coefficient as a flat list of names and values, e.g. `("swing.in_zone", 0.8)` or
+    /// `("home_run.divinity.max", 0.06)`, in a stable order.
+    pub fn coefficients(&self) -> Vec<(String, f64)> {
+        fn walk(value: &Value, name: &str, out: &mut Vec<(String, f64)>) {
+            match value {
+                Value::Object(map) => {
+                    for (key, value) in map {
+                        let name = if name.is_empty() {
+                            key.clone()
+                        } else {
+                            format!("{name}.{key}")
+                        };
+                        walk(value, &name, out);
+                    }
+                }
+                Value::Number(n) => out.extend(n.as_f64().map(|n| (name.to_string(), n))),
+                _ => {}
+            }
+        }
+
+        let mut out = Vec::new();
+        if let Ok(value) = serde_json::to_value(self) {
+            walk(&value, "", &mut out);
+        }
+        out
+    }
+
+    /// Returns a copy with the named coefficients replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a name isn't one of [`ModelParams::coefficients`].
+    pub fn with_coefficients<'a, I>(&self, coefficients: I) -> Result<ModelParams>
+    where
+        I: IntoIterator<Item = (&'a str, f64)>,
+    {
+        let mut value = serde_json::to_value(self)?;
+        for (name, x) in coefficients {
+            let pointer = format!("/{}", name.replace('.', "/"));
+            *value
+                .pointer_mut(&pointer)
+                .filter(|v| v.is_number())
+                .with_context(|| format!("unknown coefficient {name}"))? = x.into();
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Whether every [`Range`] has `min` below `max`.
+    pub fn is_valid(&self) -> bool {
+        fn walk(value: &Value) -> bool {
+            match value {
+                Value::Object(map) => {
+                    let min = map.get("min").and_then(Value::as_f64);
+                    let max = map.get("max").and_then(Value::as_f64);
+                    match (min, max) {
+                        (Some(min), Some(max)) if map.len() == 2 => min < max,
+                        _ => map.values().all(walk),
+                    }
+                }
+                _ => true,
+            }
+        }
+
+        serde_json::to_value(self).is_ok_and(|value| walk(&value))
+    }
+}
+
+impl Default for ModelParams {
+    fn default() -> ModelParams {
+        ModelParams {
+            strike_zone: StrikeZone::default(),
+            swing: Swing::default(),
+            contact: Contact::default(),
+            // An article [1] seems to indicate that foul balls occur in 40% of swings. I might be
+            // using that number wrong here though!
+            //
+            // [1]: https://www.beyondtheboxscore.com/2014/6/4/5776990/swing-rate-ball-strike-counts-swinging-strikes
+            foul: 0.4,
+            home_run: HomeRun::default(),
+            out: Out::default(),
+            // about even in MLB, and nothing is known to tilt it in Blaseball
+            fly_out: 0.5,
+            sacrifice_fly: SacrificeFly::default(),
+            single: Single::default(),
+            triple: Triple::default(),
+            extra_base: ExtraBase::default(),
+            double_play: DoublePlay::default(),
+            fielders_choice: FieldersChoice::default(),
+            steal_attempt: StealAttempt::default(),
+            steal_success: StealSuccess::default(),
+            weather: WeatherEffects::default(),
+        }
+    }
+}
+
+impl Default for StrikeZone {
+    fn default() -> StrikeZone {
+        StrikeZone {
+            moxie: Range::new(0.2, 0.8),
+            pitching: Range::new(0.0, 1.0),
+        }
+    }
+}
+
+impl Default for Swing {
+    fn default() -> Swing {
+        Swing {
+            in_zone: 0.8,
+            out_of_zone: 0.2,
+            shakespearianism: Range::new(0.0, 0.15),
+        }
+    }
+}
+
+impl Default for Contact {
+    fn default() -> Contact {
+        Contact {
+            in_zone: 0.8,
+            out_of_zone: 0.1,
+            patheticism: Range::new(0.0, 0.15),
+            thwackability: Range::new(0.0, 0.4),
+            unthwackability: Range::new(0.0, 0.4),
+        }
+    }
+}
+
+impl Default for HomeRun {
+    fn default() -> HomeRun {
+        HomeRun {
+            divinity: Range::new(0.0, 0.06),
+        }
+    }
+}
+
+impl Default for Out {
+    fn default() -> Out {
+        Out {
+            defense: Range::new(0.2, 0.6),
+            thwackability: Range::new(0.0, 0.2),
+        }
+    }
+}
+
+impl Default for SacrificeFly {
+    fn default() -> SacrificeFly {
+        SacrificeFly {
+            martyrdom: Range::new(0.2, 0.8),
+            baserunning: Range::new(0.2, 0.8),
+        }
+    }
+}
+
+impl Default for Single {
+    fn default() -> Single {
+        Single {
+            musclitude: Range::new(0.2, 0.4),
+        }
+    }
+}
+
+impl Default for Triple {
+    fn default() -> Triple {
+        Triple {
+            ground_friction: Range::new(0.1, 0.4),
+        }
+    }
+}
+
+impl Default for ExtraBase {
+    fn default() -> ExtraBase {
+        ExtraBase {
+            baserunning: Range::new(0.0, 0.5),
+        }
+    }
+}
+
+impl Default for DoublePlay {
+    fn default() -> DoublePlay {
+        DoublePlay {
+            defense: Range::new(0.0, 0.075),
+        }
+    }
+}
+
+impl Default for FieldersChoice {
+    fn default() -> FieldersChoice {
+        FieldersChoice {
+            defense: Range::new(0.0, 0.75),
+        }
+    }
+}
+
+impl Default for StealAttempt {
+    fn default() -> StealAttempt {
+        // a couple of attempts a game, with a runner on and an open base ahead for about a third
+        // of all pitches
+        StealAttempt {
+            base_thirst: Range::new(0.0, 0.04),
+        }
+    }
+}
+
+impl Default for StealSuccess {
+    fn default() -> StealSuccess {
+        StealSuccess {
+            laserlikeness: Range::new(0.4, 1.0),
+            defense: Range::new(0.4, 1.0),
+        }
+    }
+}
+
+impl Default for WeatherEffects {
+    fn default() -> WeatherEffects {
+        // roughly one effect in every eight games in the weather, at about 280 pitches a game
+        WeatherEffects {
+            incineration: 0.0005,
+            peanut: 0.0005,
+            peanut_reaction: 0.2,
+            feedback: 0.0005,
+            reverb: 0.0005,
+            blooddrain: 0.0005,
+            siphon: 0.1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ModelParams, Range};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_partial() {
+        let params: ModelParams = toml::from_str(
+            "
+            foul = 0.3
+
+            [swing]
+            in_zone = 0.7
+
+            [home_run]
+            divinity = { min = 0.01, max = 0.1 }
+            ",
+        )
+        .unwrap();
+        let default = ModelParams::default();
+        assert_approx_eq!(params.foul, 0.3);
+        assert_approx_eq!(params.swing.in_zone, 0.7);
+        assert_approx_eq!(params.swing.out_of_zone, default.swing.out_of_zone);
+        assert_eq!(params.home_run.divinity, Range::new(0.01, 0.1));
+        assert_eq!(params.contact, default.contact);
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        assert!(serde_json::from_str::<ModelParams>(r#"{"fouls": 0.3}"#).is_err());
+    }
+
+    #[test]
+    fn test_coefficients() {
+        let default = ModelParams::default();
+        let coefficients = default.coefficients();
+        assert!(coefficients.contains(&("foul".to_string(), 0.4)));
+        assert!(coefficients.contains(&("home_run.divinity.max".to_string(), 0.06)));
+
+        let params = default
+            .with_coefficients(vec![("foul", 0.3), ("home_run.divinity.max", 0.1)])
+            .unwrap();
+        assert_approx_eq!(params.foul, 0.3);
+        assert_eq!(params.home_run.divinity, Range::new(0.0, 0.1));
+        assert!(params.is_valid());
+
+        assert!(default.with_coefficients(vec![("fouls", 0.3)]).is_err());
+        assert!(default.with_coefficients(vec![("home_run", 0.3)]).is_err());
+
+        let params = default
+            .with_coefficients(vec![("home_run.divinity.min", 0.2)])
+            .unwrap();
+        assert!(!params.is_valid());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let params = ModelParams::default();
+        let s = serde_json::to_string(&params).unwrap();
+        assert_eq!(serde_json::from_str::<ModelParams>(&s).unwrap(), params);
+        let s = params.to_toml().unwrap();
+        assert_eq!(toml::from_str::<ModelParams>(&s).unwrap(), params);
+    }
+}
@@ -1,8 +1,10 @@
 use crate::database::Player;
-use crate::util::fix;
+use crate::params::ModelParams;
+use crate::util::chance;
 use rand::Rng;
 use tracing::{instrument, trace};
 
+/// The outcome of a single pitch.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Pitch {
     Ball,
@@ -16,13 +18,82 @@ pub enum Pitch {
 }
 
 impl Pitch {
-    #[instrument(name = "Pitch::simulate", skip(defense, rng))]
-    pub fn simulate(
+    /// Every outcome, in the order used by [`PitchModel::distribution`].
+    pub const ALL: [Pitch; 8] = [
+        Pitch::Ball,
+        Pitch::Strike,
+        Pitch::Foul,
+        Pitch::Out,
+        Pitch::Single,
+        Pitch::Double,
+        Pitch::Triple,
+        Pitch::Dinger,
+    ];
+
+    /// This outcome's index into a distribution.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Draws an outcome from a distribution indexed like [`Pitch::ALL`].
+    pub fn sample(distribution: &[f64; 8], rng: &mut impl Rng) -> Pitch {
+        let r: f64 = rng.gen();
+        let mut cumulative = 0.0;
+        for (pitch, p) in Pitch::ALL.iter().zip(distribution) {
+            cumulative += p;
+            if r < cumulative {
+                return *pitch;
+            }
+        }
+        // only reachable through rounding error, when r is within a hair of 1
+        Pitch::ALL[distribution.iter().rposition(|p| *p > 0.0).unwrap_or(0)]
+    }
+}
+
+/// A hypothesis for how the outcome of a pitch is decided. [`Playable::simulate_with`] runs a game
+/// with any model, so competing hypotheses can be backtested side by side.
+///
+/// [`Playable::simulate_with`]: crate::Playable::simulate_with
+pub trait PitchModel {
+    /// The exact probability of each outcome of a pitch from `pitcher` to `batter` with `defense`
+    /// in the field, indexed like [`Pitch::ALL`].
+    fn distribution(
+        &self,
+        params: &ModelParams,
+        pitcher: &Player,
+        batter: &Player,
+        defense: &[Player; 9],
+    ) -> [f64; 8];
+
+    /// Rolls the outcome of one pitch by sampling from [`PitchModel::distribution`].
+    fn pitch(
+        &self,
+        params: &ModelParams,
         pitcher: &Player,
         batter: &Player,
         defense: &[Player; 9],
         rng: &mut impl Rng,
     ) -> Pitch {
+        let distribution = self.distribution(params, pitcher, batter, defense);
+        let pitch = Pitch::sample(&distribution, rng);
+        trace!(?pitch, ?distribution);
+        pitch
+    }
+}
+
+/// Our current best guess at the pitch model.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultModel;
+
+impl PitchModel for DefaultModel {
+    #[instrument(name = "DefaultModel::distribution", skip(self, params, defense))]
+    fn distribution(
+        &self,
+        params: &ModelParams,
+        pitcher: &Player,
+        batter: &Player,
+        defense: &[Player; 9],
+    ) -> [f64; 8] {
         // Some correlations we understand so far:
         //
         // * higher thwackability correlates to more hits
@@ -41,126 +112,129 @@ impl Pitch {
         // * higher unthwackability means pitches are harder to hit
         //
         // We use the general pitcher rating where we're not sure of a correlation.
+        //
+        // Each step below is a roll that succeeds with probability `p`; values of `p` outside of
+        // 0..=1 always or never succeed.
 
         // 1. Here's the pitch. Is it in the strike zone?
-        let in_strike_zone = {
-            let p = (fix(1.0 - batter.moxie, 0.2, 0.8) + fix(pitcher.pitching(), 0.0, 1.0)) / 2.0;
-            let r: f64 = rng.gen();
-            trace!(
-                in_strike_zone = r < p,
-                %p,
-                %r,
-                %batter.moxie,
-                pitcher.pitching = %pitcher.pitching(),
-            );
-            r < p
-        };
+        let in_strike_zone = chance(f64::midpoint(
+            params.strike_zone.moxie.fix(1.0 - batter.moxie),
+            params.strike_zone.pitching.fix(pitcher.pitching()),
+        ));
 
         // 2. Is the batter going to swing at it?
-        let batter_swings = {
-            let p =
-                if in_strike_zone { 0.8 } else { 0.2 } + fix(pitcher.shakespearianism, 0.0, 0.15);
-            let r: f64 = rng.gen();
-            trace!(batter_swings = r < p, %p, %r, in_strike_zone, %pitcher.shakespearianism);
-            r < p
-        };
-
-        if !batter_swings {
-            if in_strike_zone {
-                trace!(pitch = ?Pitch::Strike);
-                return Pitch::Strike;
-            } else {
-                trace!(pitch = ?Pitch::Ball);
-                return Pitch::Ball;
-            }
-        }
+        let swing =
+            |p: f64| chance(p + params.swing.shakespearianism.fix(pitcher.shakespearianism));
+        let swings_in_zone = swing(params.swing.in_zone);
+        let swings_out_of_zone = swing(params.swing.out_of_zone);
 
         // 3. The batter swings. Do they hit it?
-        let batter_hits = {
-            let p = if in_strike_zone { 0.8 } else { 0.1 } - fix(batter.patheticism, 0.0, 0.15)
-                + fix(batter.thwackability, 0.0, 0.4)
-                - fix(pitcher.unthwackability, 0.0, 0.4);
-            let r: f64 = rng.gen();
-            trace!(
-                batter_hits = r < p,
-                %p,
-                %r,
-                in_strike_zone,
-                %batter.patheticism,
-                %batter.thwackability,
-                %pitcher.unthwackability,
-            );
-            r < p
+        let contact = |p: f64| {
+            chance(
+                p - params.contact.patheticism.fix(batter.patheticism)
+                    + params.contact.thwackability.fix(batter.thwackability)
+                    - params.contact.unthwackability.fix(pitcher.unthwackability),
+            )
         };
-        if !batter_hits {
-            trace!(pitch = ?Pitch::Strike);
-            return Pitch::Strike;
-        }
+        let contact_in_zone = contact(params.contact.in_zone);
+        let contact_out_of_zone = contact(params.contact.out_of_zone);
+
+        let ball = (1.0 - in_strike_zone) * (1.0 - swings_out_of_zone);
+        let strike = in_strike_zone * (1.0 - swings_in_zone)
+            + in_strike_zone * swings_in_zone * (1.0 - contact_in_zone)
+            + (1.0 - in_strike_zone) * swings_out_of_zone * (1.0 - contact_out_of_zone);
+        let hit = in_strike_zone * swings_in_zone * contact_in_zone
+            + (1.0 - in_strike_zone) * swings_out_of_zone * contact_out_of_zone;
 
         // 4. The batter hits the ball. Where does it go?
+        let foul = hit * chance(params.foul);
+        let fair = hit - foul;
+        let dinger = fair * chance(params.home_run.divinity.fix(batter.divinity));
+        let in_play = fair - dinger;
 
-        // An article [1] seems to indicate that foul balls occur in 40% of swings. I might be
-        // using that number wrong here though!
-        //
-        // [1]: https://www.beyondtheboxscore.com/2014/6/4/5776990/swing-rate-ball-strike-counts-swinging-strikes
-        let foul = {
-            let p = 0.4;
-            let r: f64 = rng.gen();
-            trace!(foul = r < p, %p, %r);
-            r < p
-        };
-        if foul {
-            trace!(pitch = ?Pitch::Foul);
-            return Pitch::Foul;
-        }
+        // 5. The ball is in play. A defender is picked at random to roll for the out
+        #[allow(clippy::cast_precision_loss)]
+        let out = in_play
+            * defense
+                .iter()
+                .map(|defender| {
+                    chance(
+                        params.out.defense.fix(defender.defense())
+                            + params.out.thwackability.fix(batter.thwackability),
+                    )
+                })
+                .sum::<f64>()
+            / defense.len() as f64;
+        let safe = in_play - out;
 
-        let home_run = {
-            let p = fix(batter.divinity, 0.0, 0.06);
-            let r: f64 = rng.gen();
-            trace!(home_run = r < p, %p, %r, %batter.divinity);
-            r < p
-        };
-        if home_run {
-            trace!(pitch = ?Pitch::Dinger);
-            return Pitch::Dinger;
-        }
+        let single = safe * chance(1.0 - params.single.musclitude.fix(batter.musclitude));
+        let extra_bases = safe - single;
+        let triple =
+            extra_bases * chance(params.triple.ground_friction.fix(batter.ground_friction));
+        let double = extra_bases - triple;
 
-        // 5. The ball is in play. Pick a defender at random and roll
-        let out = {
-            let defender = &defense[rng.gen_range(0, 9)];
-            let p = fix(defender.defense(), 0.2, 0.6) + fix(batter.thwackability, 0.0, 0.2);
-            let r: f64 = rng.gen();
-            trace!(out = r < p, %p, %r, defender.defense = %defender.defense(), %batter.thwackability, ?defender);
-            r < p
-        };
-        if out {
-            trace!(pitch = ?Pitch::Out);
-            return Pitch::Out;
-        }
+        trace!(
+            %in_strike_zone,
+            %swings_in_zone,
+            %swings_out_of_zone,
+            %contact_in_zone,
+            %contact_out_of_zone,
+        );
+        [ball, strike, foul, out, single, double, triple, dinger]
+    }
+}
 
-        let single = {
-            let p = 1.0 - fix(batter.musclitude, 0.2, 0.4);
-            let r: f64 = rng.gen();
-            trace!(single = r < p, %p, %r, %batter.musclitude);
-            r < p
-        };
-        if single {
-            trace!(pitch = ?Pitch::Single);
-            return Pitch::Single;
+#[cfg(test)]
+mod tests {
+    use super::{DefaultModel, Pitch, PitchModel};
+    use crate::database::{test_player, Player};
+    use crate::params::ModelParams;
+    use assert_approx_eq::assert_approx_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_distribution() {
+        let zero = test_player(0, 0.0);
+        let defense: [Player; 9] = Default::default();
+        let distribution =
+            DefaultModel.distribution(&ModelParams::default(), &zero, &zero, &defense);
+        let expected = [
+            0.48,
+            0.252,
+            0.1072,
+            0.03216,
+            0.102_912,
+            0.023_155_2,
+            0.002_572_8,
+            0.0,
+        ];
+        for (p, expected) in distribution.iter().zip(&expected) {
+            assert_approx_eq!(p, expected);
         }
+    }
 
-        let triple = {
-            let p = fix(batter.ground_friction, 0.1, 0.4);
-            let r: f64 = rng.gen();
-            trace!(triple = r < p, %p, %r, %batter.ground_friction);
-            r < p
-        };
-        if triple {
-            trace!(pitch = ?Pitch::Triple);
-            return Pitch::Triple;
+    #[test]
+    fn test_distribution_sums_to_one() {
+        let params = ModelParams::default();
+        for x in &[0.0, 0.25, 0.5, 1.0, 1.5] {
+            let player = test_player(0, *x);
+            let defense: [Player; 9] = Default::default();
+            let distribution = DefaultModel.distribution(&params, &player, &player, &defense);
+            assert!(distribution.iter().all(|p| *p >= 0.0));
+            assert_approx_eq!(distribution.iter().sum::<f64>(), 1.0);
         }
+    }
 
-        trace!(pitch = ?Pitch::Double);
-        Pitch::Double
+    #[test]
+    fn test_sample() {
+        let distribution = [0.5, 0.0, 0.25, 0.0, 0.0, 0.0, 0.25, 0.0];
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut counts = [0_u32; 8];
+        for _ in 0..10_000 {
+            counts[Pitch::sample(&distribution, &mut rng).index()] += 1;
+        }
+        for (count, p) in counts.iter().zip(&distribution) {
+            assert_approx_eq!(f64::from(*count) / 10_000.0, p, 0.02);
+        }
     }
 }
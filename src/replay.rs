@@ -0,0 +1,414 @@
+use crate::feed::{parse_feed, stolen_from, Play, PlayEvent, PlayState};
+use crate::game::{Game, Playable, State};
+use crate::params::ModelParams;
+use crate::util::AwayHome;
+use anyhow::{anyhow, Result};
+use rand::RngCore;
+use serde::Serialize;
+use std::fmt;
+
+/// What first differed between the simulation's rules and an archived game; see [`replay`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Mismatch {
+    /// The inning, or which half of it.
+    Inning,
+    Outs,
+    /// Balls or strikes.
+    Count,
+    /// Who is on which base, after every way the simulation could have moved the runners.
+    Runners,
+    Score,
+    /// The wrong batter came up.
+    Batter,
+    /// The game ended when the simulation would have kept going.
+    GameOver,
+    /// The game kept going when the simulation would have ended it.
+    NotOver,
+}
+
+/// Where an archived game first stopped following the simulation's rules.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Divergence {
+    /// The update's index in the feed.
+    pub update: usize,
+    pub text: String,
+    pub mismatch: Mismatch,
+    /// The game as the simulation's rules have it after the play.
+    pub expected: PlayState,
+    /// The game as archived.
+    pub actual: PlayState,
+}
+
+/// The result of replaying one archived game; see [`replay`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replay {
+    /// The events that matched the rules before the divergence, if any.
+    pub events: usize,
+    /// Lines the feed parser didn't understand, which were skipped.
+    pub unparsed: usize,
+    pub divergence: Option<Divergence>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Mismatch::Inning => "inning",
+            Mismatch::Outs => "outs",
+            Mismatch::Count => "count",
+            Mismatch::Runners => "runners",
+            Mismatch::Score => "score",
+            Mismatch::Batter => "batter",
+            Mismatch::GameOver => "game over",
+            Mismatch::NotOver => "not over",
+        })
+    }
+}
+
+/// Plays `updates`, one archived game of `game` in order, through the same [`State`] transitions
+/// as [`Playable::simulate`], checking the inning, outs, count, runners, score and batting order
+/// against every update until they first differ. Where the simulation rolls for how far runners
+/// advance, any outcome it could have rolled is accepted.
+///
+/// # Errors
+///
+/// Returns an error if the first update can't be resumed from; see [`Playable::situation`].
+pub fn replay(game: &Playable, updates: &[Game], params: &ModelParams) -> Result<Replay> {
+    let first = updates
+        .first()
+        .ok_or_else(|| anyhow!("no updates for game {}", game.id))?;
+    let mut state = State::resume(game, &game.situation(&first.snapshot)?);
+    let feed = parse_feed(updates);
+    let mut replay = Replay {
+        events: 0,
+        unparsed: feed.unparsed.len(),
+        divergence: None,
+    };
+
+    for event in &feed.events {
+        if let Err(mismatch) = step(game, params, &mut state, event) {
+            replay.divergence = Some(Divergence {
+                update: event.update,
+                text: updates[event.update].snapshot.last_update.clone(),
+                mismatch,
+                expected: play_state(game, &state),
+                actual: event.after.clone(),
+            });
+            break;
+        }
+        replay.events += 1;
+    }
+    Ok(replay)
+}
+
+/// Applies one event to `state` and checks the result against the archived one. A half-inning
+/// ends after its last out is checked.
+fn step(
+    game: &Playable,
+    params: &ModelParams,
+    state: &mut State<'_>,
+    event: &PlayEvent,
+) -> Result<(), Mismatch> {
+    let rules = &game.rules;
+    let lineup = state.hitting(&game.lineups);
+    let batter = &lineup[state.at_bat()];
+    let outs = *state.hitting(&rules.outs);
+    match event.play {
+        Play::PlayBall => {}
+        Play::HalfInningStart => {
+            if rules.is_complete(&state.score) {
+                return Err(Mismatch::NotOver);
+            }
+            state.start_half_inning(rules);
+        }
+        Play::GameOver => {
+            return if rules.is_complete(&state.score) {
+                Ok(())
+            } else {
+                Err(Mismatch::GameOver)
+            };
+        }
+        Play::BatterUp => {
+            if event.batter != Some(batter.id) {
+                return Err(Mismatch::Batter);
+            }
+        }
+        Play::Ball => state.balls += 1,
+        Play::Strike { .. } => state.strikes += 1,
+        Play::Foul => {
+            let strikeout = *state.hitting(&rules.strikes);
+            state.strikes = (state.strikes + 1).min(strikeout.saturating_sub(1));
+        }
+        Play::Walk => {
+            state.walk(lineup);
+            end_plate_appearance(state);
+        }
+        Play::Strikeout { .. } => {
+            state.outs += 1;
+            end_plate_appearance(state);
+        }
+        Play::GroundOut | Play::Flyout => {
+            state.outs += 1;
+            if state.outs < outs {
+                roll_runners(game, state, event, |state, rng| {
+                    if event.play == Play::Flyout {
+                        state.tag_up(lineup, params, rng);
+                    } else {
+                        state.advance(lineup, params, 0, 1, true, rng);
+                    }
+                });
+            }
+            end_plate_appearance(state);
+        }
+        Play::DoublePlay => {
+            state.outs += 2;
+            state.lead_runner_out();
+            roll_runners(game, state, event, |state, rng| {
+                state.advance(lineup, params, 0, 1, false, rng);
+            });
+            end_plate_appearance(state);
+        }
+        Play::FieldersChoice => {
+            state.outs += 1;
+            state.lead_runner_out();
+            roll_runners(game, state, event, |state, rng| {
+                state.advance(lineup, params, 1, 1, true, rng);
+                state.reach(lineup, 0);
+            });
+            end_plate_appearance(state);
+        }
+        Play::Hit { bases } => {
+            let (min, max) = match bases {
+                1 => (1, 2),
+                2 => (2, 3),
+                _ => (3, 3),
+            };
+            roll_runners(game, state, event, |state, rng| {
+                state.advance(lineup, params, min, max, true, rng);
+                state.reach(lineup, min - 1);
+            });
+            end_plate_appearance(state);
+        }
+        Play::HomeRun { .. } => {
+            // everyone scores, with nothing to roll for
+            let home = state.bases.len();
+            state.advance(lineup, params, home, home, true, &mut Flips::default());
+            state.reach(lineup, home);
+            end_plate_appearance(state);
+        }
+        Play::StolenBase { base } | Play::CaughtStealing { base } => {
+            let stolen = matches!(event.play, Play::StolenBase { .. });
+            state.outs += u8::from(!stolen);
+            state.steal(lineup, stolen_from(base, state.bases.len()), stolen);
+        }
+    }
+
+    compare(&play_state(game, state), &event.after, outs)?;
+    if state.outs >= outs {
+        // an inning can end on a caught stealing, partway through a plate appearance
+        state.outs = 0;
+        state.balls = 0;
+        state.strikes = 0;
+        state.next_half_inning();
+    }
+    Ok(())
+}
+
+fn end_plate_appearance(state: &mut State<'_>) {
+    state.balls = 0;
+    state.strikes = 0;
+    state.next_batter();
+}
+
+/// Tries `play` with every way the simulation's rolls could come out, keeping the first that
+/// leaves the runners and score as archived. If none does, keeps the one where no roll succeeds.
+fn roll_runners<'a, F>(game: &Playable, state: &mut State<'a>, event: &PlayEvent, play: F)
+where
+    F: Fn(&mut State<'a>, &mut Flips),
+{
+    let (bases, score) = (state.bases.clone(), state.score.score);
+    // one roll per runner, at most
+    for flips in 0..1 << bases.len() {
+        state.bases.clone_from(&bases);
+        state.score.score = score;
+        play(state, &mut Flips { flips, rolled: 0 });
+        let expected = play_state(game, state);
+        if expected.bases == event.after.bases && expected.score == event.after.score {
+            return;
+        }
+    }
+    state.bases = bases;
+    state.score.score = score;
+    play(state, &mut Flips::default());
+}
+
+/// Compares the states after a play, in a half-inning that ends after `outs` outs.
+fn compare(expected: &PlayState, actual: &PlayState, outs: u8) -> Result<(), Mismatch> {
+    let inning_over = expected.outs >= outs;
+    if (expected.inning, expected.bottom) != (actual.inning, actual.bottom) {
+        Err(Mismatch::Inning)
+    } else if !(expected.outs == actual.outs || inning_over && actual.outs == 0) {
+        Err(Mismatch::Outs)
+    } else if (expected.balls, expected.strikes) != (actual.balls, actual.strikes) {
+        Err(Mismatch::Count)
+    } else if !inning_over && expected.bases != actual.bases {
+        // once the inning is over, who was left on base doesn't matter
+        Err(Mismatch::Runners)
+    } else if expected.score != actual.score {
+        Err(Mismatch::Score)
+    } else {
+        Ok(())
+    }
+}
+
+fn play_state(game: &Playable, state: &State<'_>) -> PlayState {
+    let lineup = state.hitting(&game.lineups);
+    PlayState {
+        inning: state.score.inning,
+        bottom: state.score.bottom,
+        outs: state.outs,
+        balls: state.balls,
+        strikes: state.strikes,
+        bases: (0..game.rules.base_count(state.score.bottom))
+            .map(|base| {
+                state
+                    .bases
+                    .get(base)
+                    .copied()
+                    .flatten()
+                    .map(|runner| lineup[runner].id)
+            })
+            .collect(),
+        score: AwayHome {
+            away: u16::from(state.score.score.away),
+            home: u16::from(state.score.score.home),
+        },
+    }
+}
+
+/// A stand-in random number generator that makes every roll come out a chosen way: roll `i`
+/// succeeds if bit `i` of `flips` is set, unless it can't succeed at all, and fails otherwise,
+/// unless it can't fail.
+#[derive(Debug, Default)]
+struct Flips {
+    flips: u32,
+    rolled: u32,
+}
+
+impl RngCore for Flips {
+    #[allow(clippy::cast_possible_truncation)]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let success = self.flips.checked_shr(self.rolled).unwrap_or(0) & 1 == 1;
+        self.rolled += 1;
+        // the lowest and highest values `gen::<f64>()` can return
+        if success {
+            0
+        } else {
+            u64::MAX
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replay, Mismatch};
+    use crate::feed::test_update;
+    use crate::game::test_game;
+    use crate::params::ModelParams;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_replay() {
+        let game = test_game();
+        let id = Uuid::from_u128;
+        let update = test_update;
+        let updates = [
+            update("", json!({})),
+            update("Play ball!", json!({})),
+            update("Top of 1, Tigers batting.", json!({})),
+            update(
+                "Away 0 batting for the Tigers.",
+                json!({"awayBatter": id(0), "awayTeamBatterCount": 0}),
+            ),
+            update(
+                "Ball. 1-0",
+                json!({"awayBatter": id(0), "awayTeamBatterCount": 0, "atBatBalls": 1}),
+            ),
+            update(
+                "Away 0 hits a Single!",
+                json!({"awayTeamBatterCount": 0, "basesOccupied": [0], "baseRunners": [id(0)]}),
+            ),
+            update(
+                "Away 1 batting for the Tigers.",
+                json!({
+                    "awayBatter": id(1),
+                    "awayTeamBatterCount": 1,
+                    "basesOccupied": [0],
+                    "baseRunners": [id(0)],
+                }),
+            ),
+            update(
+                "Away 1 draws a walk.",
+                json!({
+                    "awayTeamBatterCount": 1,
+                    "basesOccupied": [1, 0],
+                    "baseRunners": [id(0), id(1)],
+                }),
+            ),
+            update(
+                "Away 2 batting for the Tigers.",
+                json!({
+                    "awayBatter": id(2),
+                    "awayTeamBatterCount": 2,
+                    "basesOccupied": [1, 0],
+                    "baseRunners": [id(0), id(1)],
+                }),
+            ),
+            update(
+                "Away 2 hits a 3-run home run!",
+                json!({"awayTeamBatterCount": 2, "awayScore": 3}),
+            ),
+            update(
+                "Away 3 batting for the Tigers.",
+                json!({"awayBatter": id(3), "awayTeamBatterCount": 3, "awayScore": 3}),
+            ),
+            // an archive that lost the run scored on the ground out
+            update(
+                "Away 3 hit a ground out to Wyatt Quitter.",
+                json!({"awayTeamBatterCount": 3, "awayScore": 2, "halfInningOuts": 1}),
+            ),
+        ];
+
+        let result = replay(&game, &updates, &ModelParams::default()).unwrap();
+        assert_eq!(result.events, 10);
+        assert_eq!(result.unparsed, 0);
+        let divergence = result.divergence.unwrap();
+        assert_eq!(divergence.update, 11);
+        assert_eq!(divergence.mismatch, Mismatch::Score);
+        assert_eq!(divergence.expected.score.away, 3);
+        assert_eq!(divergence.actual.score.away, 2);
+
+        let result = replay(&game, &updates[..11], &ModelParams::default()).unwrap();
+        assert_eq!(result.events, 10);
+        assert!(result.divergence.is_none());
+    }
+}
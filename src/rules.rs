@@ -0,0 +1,170 @@
+use crate::game::Score;
+use crate::util::AwayHome;
+use serde::Deserialize;
+
+/// The rules a game is played by. The counts are per team and apply while that team is batting,
+/// so a team with a fourth strike gets it against any pitcher. The default is standard Blaseball:
+/// four balls, three strikes, three outs, four bases and nine innings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "GameRules")]
+pub struct Rules {
+    /// Balls for a walk.
+    pub balls: AwayHome<u8>,
+    /// Strikes for a strikeout.
+    pub strikes: AwayHome<u8>,
+    /// Outs to end a half-inning.
+    pub outs: AwayHome<u8>,
+    /// Bases to round to score, counting home: four, or five with a fifth base.
+    pub bases: AwayHome<u8>,
+    /// Innings before extra innings.
+    pub innings: u8,
+    pub extra_innings: ExtraInnings,
+}
+
+/// What happens to a game tied after its regulation innings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraInnings {
+    /// Innings keep coming until one ends with a team ahead.
+    #[default]
+    Unlimited,
+    /// At most this many are played, after which the game ends tied.
+    Limit(u8),
+}
+
+/// The rule fields of a game record from `game-data`. Older records don't have them all, and
+/// missing or zero counts fall back to the standard ones.
+///
+/// No record says how many innings are played or how extra innings end: its `rules` field is only
+/// the id of a rules object that isn't archived. So [`Rules::innings`] and
+/// [`Rules::extra_innings`] always keep the standard nine innings and unlimited extras when read
+/// from a record, and have to be set on the [`Rules`] directly for anything else.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GameRules {
+    away_balls: Option<u8>,
+    home_balls: Option<u8>,
+    away_strikes: Option<u8>,
+    home_strikes: Option<u8>,
+    away_outs: Option<u8>,
+    home_outs: Option<u8>,
+    away_bases: Option<u8>,
+    home_bases: Option<u8>,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            balls: AwayHome { away: 4, home: 4 },
+            strikes: AwayHome { away: 3, home: 3 },
+            outs: AwayHome { away: 3, home: 3 },
+            bases: AwayHome { away: 4, home: 4 },
+            innings: 9,
+            extra_innings: ExtraInnings::Unlimited,
+        }
+    }
+}
+
+impl From<GameRules> for Rules {
+    fn from(game: GameRules) -> Rules {
+        let standard = Rules::default();
+        let count =
+            |away: Option<u8>, home: Option<u8>, standard: AwayHome<u8>, min: u8| AwayHome {
+                away: away.filter(|&n| n >= min).unwrap_or(standard.away),
+                home: home.filter(|&n| n >= min).unwrap_or(standard.home),
+            };
+        Rules {
+            balls: count(game.away_balls, game.home_balls, standard.balls, 1),
+            strikes: count(game.away_strikes, game.home_strikes, standard.strikes, 1),
+            outs: count(game.away_outs, game.home_outs, standard.outs, 1),
+            // first base and home, at least
+            bases: count(game.away_bases, game.home_bases, standard.bases, 2),
+            ..standard
+        }
+    }
+}
+
+impl Rules {
+    /// Whether the game is over at `score`, which only makes sense between half-innings: partway
+    /// through one, it has to be played out first. The home team skips the bottom of the last
+    /// inning when it's already ahead.
+    pub fn is_complete(&self, score: &Score) -> bool {
+        let (away, home) = (score.score.away, score.score.home);
+        if score.bottom {
+            score.inning.saturating_add(1) >= self.innings && home > away
+        } else if score.inning < self.innings {
+            false
+        } else {
+            match self.extra_innings {
+                ExtraInnings::Unlimited => away != home,
+                ExtraInnings::Limit(extra) => {
+                    away != home || score.inning >= self.innings.saturating_add(extra)
+                }
+            }
+        }
+    }
+
+    /// The bases a runner can stand on while the away (`bottom` false) or home team bats, which is
+    /// every base but home.
+    pub fn base_count(&self, bottom: bool) -> usize {
+        let bases = if bottom {
+            self.bases.home
+        } else {
+            self.bases.away
+        };
+        usize::from(bases).saturating_sub(1).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExtraInnings, Rules};
+    use crate::game::Score;
+    use crate::util::AwayHome;
+
+    fn score(inning: u8, bottom: bool, away: u8, home: u8) -> Score {
+        Score {
+            inning,
+            bottom,
+            score: AwayHome { away, home },
+        }
+    }
+
+    #[test]
+    fn test_from_game() {
+        let rules: Rules =
+            serde_json::from_str(r#"{"awayStrikes": 4, "homeStrikes": 3, "homeBases": 5}"#)
+                .unwrap();
+        assert_eq!(rules.strikes, AwayHome { away: 4, home: 3 });
+        assert_eq!(rules.bases, AwayHome { away: 4, home: 5 });
+        assert_eq!(rules.balls, Rules::default().balls);
+        assert_eq!(rules.base_count(true), 4);
+
+        let rules: Rules = serde_json::from_str(r#"{"awayBalls": 0, "awayBases": 1}"#).unwrap();
+        assert_eq!(rules, Rules::default());
+    }
+
+    #[test]
+    fn test_is_complete() {
+        let rules = Rules::default();
+        // the home team gets the bottom of the ninth unless it's ahead
+        assert!(!rules.is_complete(&score(8, false, 3, 2)));
+        assert!(!rules.is_complete(&score(8, true, 3, 2)));
+        assert!(rules.is_complete(&score(8, true, 2, 3)));
+        assert!(rules.is_complete(&score(9, false, 3, 2)));
+        assert!(!rules.is_complete(&score(9, false, 2, 2)));
+        assert!(!rules.is_complete(&score(7, true, 2, 3)));
+        // extra innings end after a full inning with a team ahead
+        assert!(!rules.is_complete(&score(9, true, 3, 2)));
+        assert!(!rules.is_complete(&score(30, false, 2, 2)));
+        assert!(rules.is_complete(&score(12, false, 2, 3)));
+
+        let rules = Rules {
+            innings: 5,
+            extra_innings: ExtraInnings::Limit(1),
+            ..Rules::default()
+        };
+        assert!(rules.is_complete(&score(4, true, 0, 1)));
+        assert!(!rules.is_complete(&score(5, false, 1, 1)));
+        assert!(rules.is_complete(&score(6, false, 1, 1)));
+    }
+}
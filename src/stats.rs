@@ -1,5 +1,37 @@
 use crate::database::Player;
 use crate::util::fix;
+use rand::Rng;
+use std::fmt;
+use uuid::Uuid;
+
+/// The star ratings, each computed from a handful of attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rating {
+    Batting,
+    Pitching,
+    Baserunning,
+    Defense,
+}
+
+impl Rating {
+    pub const ALL: [Rating; 4] = [
+        Rating::Batting,
+        Rating::Pitching,
+        Rating::Baserunning,
+        Rating::Defense,
+    ];
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Rating::Batting => "batting",
+            Rating::Pitching => "pitching",
+            Rating::Baserunning => "baserunning",
+            Rating::Defense => "defense",
+        })
+    }
+}
 
 fn js_round(x: f64) -> f64 {
     if x.is_sign_negative() && (x.fract() + 0.5).abs() < f64::EPSILON {
@@ -10,6 +42,7 @@ fn js_round(x: f64) -> f64 {
 }
 
 impl Player {
+    /// The player's vibes on a (zero-indexed) day, between -1 and 1.
     pub fn current_vibe(&self, day: u8) -> f64 {
         let day = f64::from(day);
         let t = 6.0 + js_round(10.0 * self.buoyancy);
@@ -18,6 +51,7 @@ impl Player {
             + 0.5 * self.cinnamon
     }
 
+    /// Adjusts the player's attributes for their vibes on a (zero-indexed) day.
     pub fn vibe_check(&mut self, day: u8) {
         let adj = self.current_vibe(day) / 5.0;
 
@@ -54,7 +88,96 @@ impl Player {
         self.watchfulness += adj;
     }
 
-    #[allow(unused)]
+    /// A newly generated player, with every attribute drawn uniformly from 0 to 1 except
+    /// tragicness, which starts at 0.1 for everyone, and even odds of a peanut allergy.
+    pub fn generate(rng: &mut impl Rng) -> Player {
+        Player {
+            id: Uuid::from_u128(rng.gen()),
+            name: "Rookie".to_string(),
+            anticapitalism: rng.gen(),
+            base_thirst: rng.gen(),
+            buoyancy: rng.gen(),
+            chasiness: rng.gen(),
+            cinnamon: rng.gen(),
+            coldness: rng.gen(),
+            continuation: rng.gen(),
+            divinity: rng.gen(),
+            ground_friction: rng.gen(),
+            indulgence: rng.gen(),
+            laserlikeness: rng.gen(),
+            martyrdom: rng.gen(),
+            moxie: rng.gen(),
+            musclitude: rng.gen(),
+            omniscience: rng.gen(),
+            overpowerment: rng.gen(),
+            patheticism: rng.gen(),
+            pressurization: rng.gen(),
+            ruthlessness: rng.gen(),
+            shakespearianism: rng.gen(),
+            tenaciousness: rng.gen(),
+            thwackability: rng.gen(),
+            tragicness: 0.1,
+            unthwackability: rng.gen(),
+            watchfulness: rng.gen(),
+            peanut_allergy: rng.gen(),
+            total_fingers: Some(10),
+            ..Player::default()
+        }
+    }
+
+    /// Whether the player has the modification `code`, like `FIREPROOF`, for any length of time.
+    pub fn has_mod(&self, code: &str) -> bool {
+        [
+            &self.perm_attr,
+            &self.seas_attr,
+            &self.week_attr,
+            &self.game_attr,
+        ]
+        .iter()
+        .any(|mods| mods.iter().any(|m| m == code))
+    }
+
+    /// Raises the attributes behind `rating` by `amount`, or lowers them if it's negative.
+    /// Patheticism and tragicness count against batting, so they move the other way. Attributes
+    /// stay at 0 or above, and the ones that count against stay at 1 or below.
+    pub fn adjust(&mut self, rating: Rating, amount: f64) {
+        let up = |x: &mut f64| *x = (*x + amount).max(0.0);
+        let down = |x: &mut f64| *x = (*x - amount).clamp(0.0, 1.0);
+        match rating {
+            Rating::Batting => {
+                down(&mut self.tragicness);
+                up(&mut self.thwackability);
+                up(&mut self.moxie);
+                up(&mut self.divinity);
+                up(&mut self.musclitude);
+                down(&mut self.patheticism);
+                up(&mut self.martyrdom);
+            }
+            Rating::Pitching => {
+                up(&mut self.shakespearianism);
+                up(&mut self.unthwackability);
+                up(&mut self.coldness);
+                up(&mut self.overpowerment);
+                up(&mut self.ruthlessness);
+            }
+            Rating::Baserunning => {
+                up(&mut self.laserlikeness);
+                up(&mut self.continuation);
+                up(&mut self.base_thirst);
+                up(&mut self.indulgence);
+                up(&mut self.ground_friction);
+            }
+            Rating::Defense => {
+                up(&mut self.omniscience);
+                up(&mut self.tenaciousness);
+                up(&mut self.watchfulness);
+                up(&mut self.anticapitalism);
+                up(&mut self.chasiness);
+            }
+        }
+    }
+
+    /// The star rating formulas from the site, divided by 5.
     pub fn batting(&self) -> f64 {
         (1.0 - self.tragicness).powf(0.01)
             * self.thwackability.powf(0.35)
@@ -91,8 +214,9 @@ impl Player {
 }
 
 #[cfg(test)]
+#[allow(clippy::unreadable_literal)]
 mod tests {
-    use super::{js_round, Player};
+    use super::{js_round, Player, Rating};
     use assert_approx_eq::assert_approx_eq;
 
     #[test]
@@ -135,6 +259,7 @@ mod tests {
             tragicness: 0.1,
             unthwackability: 0.3801831639063583,
             watchfulness: 0.6369065120599864,
+            ..Player::default()
         };
         assert_approx_eq!(player.current_vibe(9), -0.14020491564481974, f64::EPSILON);
         assert_approx_eq!(player.batting(), 1.1112415068761758, f64::EPSILON);
@@ -142,4 +267,23 @@ mod tests {
         assert_approx_eq!(player.baserunning(), 0.7318167167251749, f64::EPSILON);
         assert_approx_eq!(player.defense(), 0.6978296398191142, f64::EPSILON);
     }
+
+    #[test]
+    fn test_adjust() {
+        let mut rng = rand::rngs::mock::StepRng::new(1 << 62, 1 << 60);
+        let mut player = Player::generate(&mut rng);
+        let before = player.clone();
+        for rating in Rating::ALL {
+            player.adjust(rating, 0.1);
+        }
+        assert!(player.batting() > before.batting());
+        assert!(player.pitching() > before.pitching());
+        assert!(player.baserunning() > before.baserunning());
+        assert!(player.defense() > before.defense());
+
+        player.adjust(Rating::Batting, -10.0);
+        assert_approx_eq!(player.thwackability, 0.0);
+        assert_approx_eq!(player.patheticism, 1.0);
+        assert_approx_eq!(player.batting(), 0.0);
+    }
 }
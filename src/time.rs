@@ -1,5 +1,7 @@
 use chrono::{Duration, TimeZone, Utc};
 
+/// The approximate start time of games on a (zero-indexed) season and day, in milliseconds since
+/// the Unix epoch.
 // note: only works for season 4 and on
 #[allow(clippy::cast_sign_loss, clippy::module_name_repetitions)]
 pub fn game_time(season: u16, day: u8) -> u64 {
@@ -13,10 +15,10 @@ pub fn game_time(season: u16, day: u8) -> u64 {
             + Duration::hours(i64::from(day))
     };
 
-    if season == 3 && day >= 59 && day < 99 {
+    if season == 3 && (59..99).contains(&day) {
         date = date + Duration::hours(10);
     }
-    if season == 3 && day >= 88 && day < 99 {
+    if season == 3 && (88..99).contains(&day) {
         date = date + Duration::hours(3);
     }
 
@@ -26,6 +28,7 @@ pub fn game_time(season: u16, day: u8) -> u64 {
 
 #[cfg(test)]
 #[test]
+#[allow(clippy::unreadable_literal)]
 fn test_game_time() {
     assert_eq!(game_time(3, 0), 1598284800000);
     assert_eq!(game_time(3, 32), 1598400000000);
@@ -1,3 +1,5 @@
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
 use std::fmt;
 use uuid::Uuid;
 
@@ -9,7 +11,17 @@ pub fn halfuuid(uuid: Uuid) -> u64 {
 
 pub fn fix(x: f64, min: f64, max: f64) -> f64 {
     debug_assert!(min < max);
-    (x * (max - min) + min).max(0.0).min(1.0)
+    (x * (max - min) + min).clamp(0.0, 1.0)
+}
+
+/// The probability that a roll of `r < p` succeeds for a uniform `r` in 0..1. A NaN `p` (from an
+/// attribute that's gone negative) never succeeds.
+pub fn chance(p: f64) -> f64 {
+    if p.is_nan() {
+        0.0
+    } else {
+        p.clamp(0.0, 1.0)
+    }
 }
 
 #[cfg(test)]
@@ -25,21 +37,141 @@ fn test_fix() {
 
 // =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
 
+/// Running score of win predictions against actual results.
 #[derive(Debug, Default)]
 pub struct Accuracy {
     len: u32,
     correct: u32,
     error: f64,
+    log_loss: f64,
+    buckets: [Bucket; BUCKETS as usize],
+}
+
+/// Predictions are grouped into this many odds buckets of equal width for calibration.
+const BUCKETS: u8 = 10;
+
+/// Predictions are clamped this far away from 0 and 1 so a single confident miss doesn't make
+/// the log loss infinite.
+const LOG_LOSS_EPSILON: f64 = 1e-15;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Bucket {
+    len: u32,
+    expected: f64,
+    actual: u32,
+}
+
+/// How often the away team won when predicted to win with odds in `min..max`.
+#[derive(Debug, Serialize)]
+pub struct Calibration {
+    pub min: f64,
+    pub max: f64,
+    pub games: u32,
+    /// The mean prediction in this bucket.
+    pub expected: f64,
+    /// The fraction of games in this bucket the away team won.
+    pub actual: f64,
 }
 
 impl Accuracy {
+    /// Records a prediction that the away team wins with probability `expected`; `actual` is 1
+    /// if they did and 0 otherwise.
     pub fn record(&mut self, expected: f64, actual: u8) {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let index = ((expected * f64::from(BUCKETS)) as usize).min(usize::from(BUCKETS) - 1);
+        let bucket = &mut self.buckets[index];
+        bucket.len += 1;
+        bucket.expected += expected;
+        bucket.actual += u32::from(actual);
+
         let actual = f64::from(actual);
         self.len += 1;
         if (expected - actual).abs() <= 0.5 {
             self.correct += 1;
         }
         self.error += (actual - expected).powi(2);
+        let p = expected.clamp(LOG_LOSS_EPSILON, 1.0 - LOG_LOSS_EPSILON);
+        self.log_loss -= actual * p.ln() + (1.0 - actual) * (1.0 - p).ln();
+    }
+
+    /// The number of predictions recorded.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Whether no predictions have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The fraction of games where the predicted favorite won.
+    pub fn correct(&self) -> f64 {
+        f64::from(self.correct) / f64::from(self.len)
+    }
+
+    /// The mean squared error of the predictions (the Brier score).
+    pub fn error(&self) -> f64 {
+        self.error / f64::from(self.len)
+    }
+
+    /// The mean negative log-likelihood of the actual results.
+    pub fn log_loss(&self) -> f64 {
+        self.log_loss / f64::from(self.len)
+    }
+
+    /// How far each bucket's mean prediction is from its actual win rate, weighted by games.
+    /// Lower is better.
+    pub fn reliability(&self) -> f64 {
+        self.buckets
+            .iter()
+            .filter(|b| b.len > 0)
+            .map(|b| {
+                let len = f64::from(b.len);
+                len * (b.expected / len - f64::from(b.actual) / len).powi(2)
+            })
+            .sum::<f64>()
+            / f64::from(self.len)
+    }
+
+    /// How far each bucket's actual win rate is from the overall win rate, weighted by games.
+    /// Higher is better.
+    pub fn resolution(&self) -> f64 {
+        let base_rate = self.base_rate();
+        self.buckets
+            .iter()
+            .filter(|b| b.len > 0)
+            .map(|b| {
+                f64::from(b.len) * (f64::from(b.actual) / f64::from(b.len) - base_rate).powi(2)
+            })
+            .sum::<f64>()
+            / f64::from(self.len)
+    }
+
+    /// The Brier score of always predicting the overall win rate. The Brier score is roughly
+    /// `reliability - resolution + uncertainty`, exactly so if every prediction in a bucket is the
+    /// same.
+    pub fn uncertainty(&self) -> f64 {
+        let base_rate = self.base_rate();
+        base_rate * (1.0 - base_rate)
+    }
+
+    fn base_rate(&self) -> f64 {
+        f64::from(self.buckets.iter().map(|b| b.actual).sum::<u32>()) / f64::from(self.len)
+    }
+
+    /// The calibration table, skipping empty buckets.
+    pub fn calibration(&self) -> Vec<Calibration> {
+        (0_u32..)
+            .zip(&self.buckets)
+            .filter(|(_, b)| b.len > 0)
+            .map(|(i, b)| Calibration {
+                min: f64::from(i) / f64::from(BUCKETS),
+                max: f64::from(i + 1) / f64::from(BUCKETS),
+                games: b.len,
+                expected: b.expected / f64::from(b.len),
+                actual: f64::from(b.actual) / f64::from(b.len),
+            })
+            .collect()
     }
 }
 
@@ -47,22 +179,86 @@ impl fmt::Display for Accuracy {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "correct: {}, error: {}",
-            f64::from(self.correct) / f64::from(self.len),
-            self.error / f64::from(self.len)
+            "correct: {}, error: {}, log loss: {}",
+            self.correct(),
+            self.error(),
+            self.log_loss()
         )
     }
 }
 
+impl Serialize for Accuracy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Accuracy", 8)?;
+        s.serialize_field("games", &self.len)?;
+        s.serialize_field("correct", &self.correct())?;
+        s.serialize_field("error", &self.error())?;
+        s.serialize_field("log_loss", &self.log_loss())?;
+        s.serialize_field("reliability", &self.reliability())?;
+        s.serialize_field("resolution", &self.resolution())?;
+        s.serialize_field("uncertainty", &self.uncertainty())?;
+        s.serialize_field("calibration", &self.calibration())?;
+        s.end()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_accuracy() {
+    use assert_approx_eq::assert_approx_eq;
+
+    let mut accuracy = Accuracy::default();
+    for (expected, actual) in &[
+        (0.25, 0),
+        (0.25, 1),
+        (0.25, 0),
+        (0.25, 0),
+        (0.85, 1),
+        (0.85, 0),
+    ] {
+        accuracy.record(*expected, *actual);
+    }
+    assert_approx_eq!(accuracy.correct(), 4.0 / 6.0);
+    assert_approx_eq!(
+        accuracy.log_loss(),
+        -(3.0 * 0.75_f64.ln() + 0.25_f64.ln() + 0.85_f64.ln() + 0.15_f64.ln()) / 6.0
+    );
+    // every prediction in a bucket is the same, so the decomposition is exact
+    assert_approx_eq!(
+        accuracy.reliability() - accuracy.resolution() + accuracy.uncertainty(),
+        accuracy.error()
+    );
+
+    let calibration = accuracy.calibration();
+    assert_eq!(calibration.len(), 2);
+    assert_eq!(calibration[0].games, 4);
+    assert_approx_eq!(calibration[0].min, 0.2);
+    assert_approx_eq!(calibration[0].actual, 0.25);
+    assert_approx_eq!(calibration[1].expected, 0.85);
+}
+
 // =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
 
-#[derive(Debug, Default, Clone, Copy)]
+/// A pair of values, one for each team.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
 pub struct AwayHome<T> {
     pub away: T,
     pub home: T,
 }
 
 impl<T> AwayHome<T> {
+    /// Applies `f` to both values.
+    pub fn map<F, U>(&self, f: F) -> AwayHome<U>
+    where
+        F: Fn(&T) -> U,
+    {
+        AwayHome {
+            away: f(&self.away),
+            home: f(&self.home),
+        }
+    }
+
+    /// Applies `f` to both values, returning `None` if either result is `None`.
     pub fn map_opt<F, U>(&self, f: F) -> Option<AwayHome<U>>
     where
         F: Fn(&T) -> Option<U>,
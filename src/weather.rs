@@ -0,0 +1,430 @@
+use crate::database::Player;
+use crate::event::GameEvent;
+use crate::game::{Playable, State};
+use crate::params::WeatherEffects;
+use crate::stats::Rating;
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt;
+
+/// The weather a game is played in, from the `weather` field of game records. Weathers without an
+/// effect in the simulation are still told apart, so games can be grouped by them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(from = "u8")]
+pub enum Weather {
+    #[default]
+    Void,
+    /// Takes 10 runs off a team's score when it gets to 10.
+    Sun2,
+    Overcast,
+    Rainy,
+    Sandstorm,
+    Snowy,
+    Acidic,
+    /// Incinerates players, unless they're fireproof, and replaces them with newly generated
+    /// ones.
+    SolarEclipse,
+    Glitter,
+    /// Moves attributes from one player to another.
+    Blooddrain,
+    /// Feeds players peanuts, which lower the attributes of those allergic to them and raise
+    /// everyone else's.
+    Peanuts,
+    Birds,
+    /// Swaps the batter and pitcher between teams.
+    Feedback,
+    /// Shuffles lineups.
+    Reverb,
+    /// A weather from after season 5, which has no effect here.
+    Other(u8),
+}
+
+impl From<u8> for Weather {
+    fn from(id: u8) -> Weather {
+        match id {
+            0 => Weather::Void,
+            1 => Weather::Sun2,
+            2 => Weather::Overcast,
+            3 => Weather::Rainy,
+            4 => Weather::Sandstorm,
+            5 => Weather::Snowy,
+            6 => Weather::Acidic,
+            7 => Weather::SolarEclipse,
+            8 => Weather::Glitter,
+            9 => Weather::Blooddrain,
+            10 => Weather::Peanuts,
+            11 => Weather::Birds,
+            12 => Weather::Feedback,
+            13 => Weather::Reverb,
+            _ => Weather::Other(id),
+        }
+    }
+}
+
+impl fmt::Display for Weather {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Weather::Void => "Void",
+            Weather::Sun2 => "Sun 2",
+            Weather::Overcast => "Overcast",
+            Weather::Rainy => "Rainy",
+            Weather::Sandstorm => "Sandstorm",
+            Weather::Snowy => "Snowy",
+            Weather::Acidic => "Acidic",
+            Weather::SolarEclipse => "Solar Eclipse",
+            Weather::Glitter => "Glitter",
+            Weather::Blooddrain => "Blooddrain",
+            Weather::Peanuts => "Peanuts",
+            Weather::Birds => "Birds",
+            Weather::Feedback => "Feedback",
+            Weather::Reverb => "Reverb",
+            Weather::Other(id) => return write!(f, "weather {id}"),
+        })
+    }
+}
+
+impl Weather {
+    /// Whether the weather can change anything in a game, given the chances in `params`.
+    pub fn has_effect(self, params: &WeatherEffects) -> bool {
+        self == Weather::Sun2 || self.chance(params) > 0.0
+    }
+
+    /// The chance of the weather's effect before each pitch, or zero if it has none.
+    fn chance(self, params: &WeatherEffects) -> f64 {
+        match self {
+            Weather::SolarEclipse => params.incineration,
+            Weather::Peanuts => params.peanut,
+            Weather::Feedback => params.feedback,
+            Weather::Reverb => params.reverb,
+            Weather::Blooddrain => params.blooddrain,
+            _ => 0.0,
+        }
+    }
+
+    /// Rolls for the weather's effect before a pitch, changing the players in `game` if it
+    /// happens. Nothing is rolled when the effect has no chance, so such games play out as if the
+    /// weather had no effect at all.
+    pub(crate) fn before_pitch(
+        self,
+        params: &WeatherEffects,
+        game: &mut Cow<'_, Playable>,
+        state: &mut State<'_>,
+        rng: &mut impl Rng,
+    ) {
+        let chance = self.chance(params);
+        if chance <= 0.0 || rng.gen::<f64>() >= chance {
+            return;
+        }
+
+        let game = game.to_mut();
+        if self == Weather::Reverb {
+            let home = rng.gen();
+            let lineup = if home {
+                &mut game.lineups.home
+            } else {
+                &mut game.lineups.away
+            };
+            let mut order = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+            order.shuffle(rng);
+            let old = lineup.clone();
+            for (player, &position) in lineup.iter_mut().zip(&order) {
+                *player = old[position].clone();
+            }
+            state.reorder(home, &order);
+            state.emit(GameEvent::Reverb { home });
+            return;
+        }
+
+        let (batter, pitcher) = matchup(game, state);
+        // whoever it happens to, the other one is the blooddrain's victim
+        let (player, other) = if rng.gen() {
+            (batter, pitcher)
+        } else {
+            (pitcher, batter)
+        };
+        match self {
+            Weather::SolarEclipse => {
+                if player.has_mod("FIREPROOF") {
+                    return;
+                }
+                let victim = std::mem::replace(player, Player::generate(rng));
+                state.emit(GameEvent::Incineration {
+                    victim: &victim,
+                    replacement: player,
+                });
+            }
+            Weather::Peanuts => {
+                let allergic = player.peanut_allergy;
+                let amount = if allergic {
+                    -params.peanut_reaction
+                } else {
+                    params.peanut_reaction
+                };
+                for rating in Rating::ALL {
+                    player.adjust(rating, amount);
+                }
+                state.emit(GameEvent::PeanutReaction { player, allergic });
+            }
+            Weather::Feedback => {
+                std::mem::swap(player, other);
+                let (batter, pitcher) = matchup(game, state);
+                // they've swapped places, so the new pitcher was the batter
+                state.emit(GameEvent::Feedback {
+                    batter: pitcher,
+                    pitcher: batter,
+                });
+            }
+            Weather::Blooddrain => {
+                let rating = *Rating::ALL.choose(rng).unwrap_or(&Rating::Batting);
+                player.adjust(rating, params.siphon);
+                other.adjust(rating, -params.siphon);
+                state.emit(GameEvent::Blooddrain {
+                    drainer: player,
+                    victim: other,
+                    rating,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies the weather's effect on the score, after anything that can score runs: a plate
+    /// appearance or a steal.
+    pub(crate) fn after_scoring(self, state: &mut State<'_>) {
+        if self == Weather::Sun2 {
+            let score = if state.is_top() {
+                &mut state.score.score.away
+            } else {
+                &mut state.score.score.home
+            };
+            if *score >= 10 {
+                *score -= 10;
+                state.emit(GameEvent::Sun2);
+            }
+        }
+    }
+}
+
+/// The batter up and the pitcher facing them.
+fn matchup<'a>(game: &'a mut Playable, state: &State<'_>) -> (&'a mut Player, &'a mut Player) {
+    let at_bat = state.at_bat();
+    if state.is_top() {
+        (&mut game.lineups.away[at_bat], &mut game.pitchers.home)
+    } else {
+        (&mut game.lineups.home[at_bat], &mut game.pitchers.away)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Weather;
+    use crate::event::GameEvent;
+    use crate::game::{test_game, Playable, Score};
+    use crate::params::{ModelParams, WeatherEffects};
+    use crate::pitch::DefaultModel;
+    use crate::util::AwayHome;
+
+    fn game(weather: Weather, thwackability: f64) -> Playable {
+        let mut game = test_game();
+        game.weather = weather;
+        for player in game.lineups.away.iter_mut().chain(&mut game.lineups.home) {
+            player.thwackability = thwackability;
+        }
+        game
+    }
+
+    #[test]
+    fn test_from_id() {
+        let weather = |json| serde_json::from_str::<Weather>(json).unwrap();
+        assert_eq!(weather("1"), Weather::Sun2);
+        assert_eq!(weather("7"), Weather::SolarEclipse);
+        assert_eq!(weather("13"), Weather::Reverb);
+        assert_eq!(weather("19"), Weather::Other(19));
+        assert_eq!(Weather::Other(19).to_string(), "weather 19");
+        assert_eq!(Weather::default(), Weather::Void);
+    }
+
+    #[test]
+    fn test_no_chance() {
+        let params = ModelParams {
+            weather: WeatherEffects {
+                incineration: 0.0,
+                peanut: 0.0,
+                feedback: 0.0,
+                reverb: 0.0,
+                blooddrain: 0.0,
+                ..WeatherEffects::default()
+            },
+            ..ModelParams::default()
+        };
+        for weather in [
+            Weather::SolarEclipse,
+            Weather::Peanuts,
+            Weather::Feedback,
+            Weather::Reverb,
+            Weather::Blooddrain,
+        ] {
+            assert!(!weather.has_effect(&params.weather));
+            assert!(weather.has_effect(&WeatherEffects::default()));
+            for seed in 0..10 {
+                let score = game(weather, 0.6).simulate_with(&DefaultModel, &params, seed);
+                let sunny = game(Weather::Void, 0.6).simulate_with(&DefaultModel, &params, seed);
+                assert_eq!(score.score, sunny.score);
+            }
+        }
+    }
+
+    #[test]
+    fn test_effects() {
+        let params = ModelParams {
+            weather: WeatherEffects {
+                incineration: 0.05,
+                peanut: 0.05,
+                feedback: 0.05,
+                reverb: 0.05,
+                blooddrain: 0.05,
+                ..WeatherEffects::default()
+            },
+            ..ModelParams::default()
+        };
+        for weather in [
+            Weather::SolarEclipse,
+            Weather::Peanuts,
+            Weather::Feedback,
+            Weather::Reverb,
+            Weather::Blooddrain,
+        ] {
+            let game = game(weather, 0.6);
+            let mut effects = 0;
+            for seed in 0..5 {
+                let mut runs = AwayHome { away: 0, home: 0 };
+                let score = game.simulate_observed(
+                    &DefaultModel,
+                    &params,
+                    seed,
+                    &mut |event: &GameEvent<'_>, score: &Score| match *event {
+                        GameEvent::Incineration {
+                            victim,
+                            replacement,
+                        } => {
+                            assert_eq!(weather, Weather::SolarEclipse);
+                            assert_ne!(victim.id, replacement.id);
+                            effects += 1;
+                        }
+                        GameEvent::PeanutReaction { player, allergic } => {
+                            assert_eq!(weather, Weather::Peanuts);
+                            assert_eq!(allergic, player.peanut_allergy);
+                            effects += 1;
+                        }
+                        GameEvent::Feedback { batter, pitcher } => {
+                            assert_eq!(weather, Weather::Feedback);
+                            assert_ne!(batter.id, pitcher.id);
+                            effects += 1;
+                        }
+                        GameEvent::Reverb { .. } => {
+                            assert_eq!(weather, Weather::Reverb);
+                            effects += 1;
+                        }
+                        GameEvent::Blooddrain {
+                            drainer, victim, ..
+                        } => {
+                            assert_eq!(weather, Weather::Blooddrain);
+                            assert_ne!(drainer.id, victim.id);
+                            effects += 1;
+                        }
+                        GameEvent::Run { .. } => {
+                            if score.bottom {
+                                runs.home += 1;
+                            } else {
+                                runs.away += 1;
+                            }
+                        }
+                        _ => {}
+                    },
+                );
+                assert_eq!(runs, score.score);
+            }
+            assert!(effects > 0, "no effects in {}", weather);
+        }
+    }
+
+    #[test]
+    fn test_fireproof() {
+        let mut game = game(Weather::SolarEclipse, 0.6);
+        let players = game.lineups.away.iter_mut().chain(&mut game.lineups.home);
+        for player in players.chain([&mut game.pitchers.away, &mut game.pitchers.home]) {
+            player.perm_attr.push("FIREPROOF".to_string());
+        }
+        let params = ModelParams {
+            weather: WeatherEffects {
+                incineration: 1.0,
+                ..WeatherEffects::default()
+            },
+            ..ModelParams::default()
+        };
+        game.simulate_observed(
+            &DefaultModel,
+            &params,
+            0,
+            &mut |event: &GameEvent<'_>, _: &Score| {
+                assert!(!matches!(event, GameEvent::Incineration { .. }));
+            },
+        );
+    }
+
+    #[test]
+    fn test_sun_2() {
+        let mut game = game(Weather::Sun2, 2.0);
+        // pitchers who can't get anyone out, so the runs pile up
+        game.pitchers = AwayHome::default();
+        let params = ModelParams::default();
+        let mut smiles = 0;
+        for seed in 0..20 {
+            let score = game.simulate_observed(
+                &DefaultModel,
+                &params,
+                seed,
+                &mut |event: &GameEvent<'_>, _: &Score| {
+                    if let GameEvent::Sun2 = event {
+                        smiles += 1;
+                    }
+                },
+            );
+            assert!(score.score.away < 10 && score.score.home < 10);
+        }
+        assert!(smiles > 0);
+    }
+
+    #[test]
+    fn test_sun_2_stolen_home() {
+        let mut game = game(Weather::Sun2, 2.0);
+        game.pitchers = AwayHome::default();
+        for player in game.lineups.away.iter_mut().chain(&mut game.lineups.home) {
+            player.base_thirst = 1.0;
+            player.laserlikeness = 1.0;
+        }
+        let params = ModelParams::default();
+        // a tenth run stolen in the middle of a plate appearance sets the Sun 2 off right away
+        let mut stolen = 0;
+        for seed in 0..20 {
+            game.simulate_observed(
+                &DefaultModel,
+                &params,
+                seed,
+                &mut |event: &GameEvent<'_>, score: &Score| match event {
+                    GameEvent::Run { batter: None, .. }
+                        if score.score.away == 10 || score.score.home == 10 =>
+                    {
+                        stolen += 1;
+                    }
+                    GameEvent::Pitch { .. } => {
+                        assert!(score.score.away < 10 && score.score.home < 10);
+                    }
+                    _ => {}
+                },
+            );
+        }
+        assert!(stolen > 0);
+    }
+}